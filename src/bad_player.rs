@@ -1,5 +1,7 @@
-use crate::board::Board;
+#![allow(dead_code)]
+
 use crate::board::Side;
+use crate::board::{Board, Move};
 use crate::player::Player;
 pub struct BadPlayer {
     name: String,
@@ -21,15 +23,14 @@ impl Player for BadPlayer {
         false
     }
 
-    fn choose_move(&self, b: &Board, s: Side) -> i32 {
-        // choose the first legal move. Returns -1 if there is no legal move
+    fn choose_move(&self, b: &Board, s: Side) -> Option<Move> {
+        // choose the first legal move. Returns None if there is no legal move
 
-        for hole in 1..=b.holes() {
-            if b.beans(s, hole) > 0 {
-                println!("{} chooses hole {}", self.name, hole);
-                return hole;
-            }
-        }
-        -1
+        b.legal_moves(s).next()
+    }
+
+    fn should_swap(&self, b: &Board, s: Side) -> bool {
+        // swap if the opponent's pot is ahead of ours
+        b.beans(s.opponent(), 0) > b.beans(s, 0)
     }
 }
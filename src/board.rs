@@ -1,5 +1,13 @@
 #![allow(dead_code)]
 
+use std::fmt;
+use std::str::FromStr;
+
+use crate::pits::{InlinePits, Pits, VecPits};
+use crate::rng::Rng;
+use crate::rules::{CaptureRule, Direction, Rules, Ruleset, SowingRule, SweepRule};
+use crate::zobrist;
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum Side {
     North,
@@ -15,238 +23,2148 @@ impl Side {
     }
 }
 
-#[derive(Clone)]
-pub struct Board {
-    north_holes: Vec<i32>,
-    south_holes: Vec<i32>,
-    north_pot: i32,
-    south_pot: i32,
-    num_holes: i32,
+// a move: the number of the hole (from 1 up) that a player picks up and sows, and which way it
+// is sown where the rules let the player choose (SowingRule::PlayersChoice). Move::new rejects
+// numbers that can never be a move; Board::check_sowing also checks the move against a board.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Move {
+    hole: i32,
+    // None to sow the way the rules always do
+    direction: Option<Direction>,
 }
 
-impl Board {
-    pub fn new(n_holes: i32, num_initial_beans_per_hole: i32) -> Self {
-        let n_holes = if n_holes <= 0 { 1 } else { n_holes };
+impl Move {
+    pub fn new(hole: i32) -> Result<Move, BoardError> {
+        if hole < 1 {
+            return Err(BoardError::NoSuchHole(hole));
+        }
+        Ok(Move {
+            hole,
+            direction: None,
+        })
+    }
 
-        Board {
-            north_holes: vec![num_initial_beans_per_hole; n_holes as usize],
-            south_holes: vec![num_initial_beans_per_hole; n_holes as usize],
-            north_pot: 0,
-            south_pot: 0,
-            num_holes: n_holes,
+    // the same hole, sown the given way.
+    pub fn towards(self, direction: Direction) -> Move {
+        Move {
+            direction: Some(direction),
+            ..self
         }
     }
 
-    pub fn holes(&self) -> i32 {
-        self.num_holes
+    pub fn hole(&self) -> i32 {
+        self.hole
     }
-    pub fn beans(&self, s: Side, hole: i32) -> i32 {
-        if hole < 0 || hole > self.holes() {
-            return -1;
+
+    pub fn direction(&self) -> Option<Direction> {
+        self.direction
+    }
+
+    // the same move on a board with num_holes holes a side after Board::mirrored. The direction
+    // is kept: mirroring turns the board round without turning it over.
+    pub fn mirrored(self, num_holes: i32) -> Move {
+        Move {
+            hole: num_holes + 1 - self.hole,
+            ..self
         }
-        if hole == 0 {
-            match s {
-                Side::North => return self.north_pot,
-                Side::South => return self.south_pot,
-            }
+    }
+}
+
+// a move is written as its hole number, followed by "cw" (clockwise) or "ccw"
+// (counter-clockwise) if it has a direction, e.g. "3" or "3cw".
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.hole)?;
+        match self.direction {
+            None => Ok(()),
+            Some(Direction::CounterClockwise) => write!(f, "ccw"),
+            Some(Direction::Clockwise) => write!(f, "cw"),
         }
-        match s {
-            Side::North => {
-                return *self.north_holes.get((hole - 1) as usize).unwrap();
+    }
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (hole, direction) = if let Some(hole) = text.strip_suffix("ccw") {
+            (hole, Some(Direction::CounterClockwise))
+        } else if let Some(hole) = text.strip_suffix("cw") {
+            (hole, Some(Direction::Clockwise))
+        } else {
+            (text, None)
+        };
+        let hole = hole
+            .parse()
+            .map_err(|_| format!("'{}' is not a move", text))?;
+        let mv = Move::new(hole).map_err(|e| e.to_string())?;
+        Ok(match direction {
+            Some(direction) => mv.towards(direction),
+            None => mv,
+        })
+    }
+}
+
+// why a board operation could not be done.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BoardError {
+    // the board has no such hole (or, where a pot is allowed, no such pot); holds the number
+    NoSuchHole(i32),
+    // a hole or pot cannot hold a negative number of beans; holds the number
+    NegativeBeans(i32),
+    // the hole chosen to sow holds no beans
+    EmptyHole(i32),
+    // the opponent has no beans and sowing this hole would not give it any, which the rules
+    // forbid
+    MustFeed(i32),
+    // the board has too many holes or beans for the storage it is being converted to
+    TooLarge,
+    // a board must have at least one hole a side; holds the number asked for
+    BadHoleCount(i32),
+    // the rules sow every move the same way and this move asked for the other; holds the hole
+    WrongDirection(i32),
+    // the rules leave the direction to the player and this move did not give one; holds the hole
+    NoDirection(i32),
+    // a random deal was given weights that cannot deal the beans (see layout::Deal)
+    BadWeights,
+    // the two sides were given different numbers of holes
+    SidesDiffer { north: usize, south: usize },
+    // the beans on the board do not add up to the number there should be
+    WrongTotal { expected: i32, found: i32 },
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoardError::NoSuchHole(hole) => write!(f, "there is no hole {}", hole),
+            BoardError::NegativeBeans(n) => write!(f, "a hole or pot cannot hold {} beans", n),
+            BoardError::EmptyHole(hole) => write!(f, "there are no beans in hole {}", hole),
+            BoardError::MustFeed(hole) => {
+                write!(f, "hole {} does not give the opponent any beans", hole)
+            }
+            BoardError::TooLarge => write!(f, "the board has too many holes or beans"),
+            BoardError::BadHoleCount(n) => {
+                write!(f, "the number of holes must be positive, not {}", n)
+            }
+            BoardError::WrongDirection(hole) => {
+                write!(f, "hole {} cannot be sown that way under these rules", hole)
             }
-            Side::South => {
-                return *self.south_holes.get((hole - 1) as usize).unwrap();
+            BoardError::NoDirection(hole) => {
+                write!(f, "hole {} needs a direction to be sown in", hole)
             }
+            BoardError::BadWeights => write!(
+                f,
+                "there must be one weight per hole, none negative, not all 0 and not adding up to \
+                 more than {}",
+                i32::MAX
+            ),
+            BoardError::SidesDiffer { north, south } => write!(
+                f,
+                "North has {} holes but South has {}; both sides need the same number",
+                north, south
+            ),
+            BoardError::WrongTotal { expected, found } => write!(
+                f,
+                "there are {} beans on the board instead of {}",
+                found, expected
+            ),
         }
     }
-    pub fn beans_in_play(&self, s: Side) -> i32 {
-        let v = match s {
-            Side::North => self.north_holes.iter(),
-            Side::South => self.south_holes.iter(),
+}
+
+// what happened when one hole was sown by Board::apply_move.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct MoveOutcome {
+    // where the last bean was sown (hole 0 being the pot)
+    pub end_side: Side,
+    pub end_hole: i32,
+    // beans moved into the mover's pot by a capture, including the capturing bean; 0 if none
+    pub captured: i32,
+    // true if the side that moved gets to sow again
+    pub extra_turn: bool,
+    // the side whose turn it is now (or would be, had the game not ended)
+    pub next_to_move: Side,
+    // set if the move ended the game, describing the final sweep
+    pub sweep: Option<Sweep>,
+}
+
+impl MoveOutcome {
+    pub fn game_over(&self) -> bool {
+        self.sweep.is_some()
+    }
+}
+
+// what a move would do, from Board::preview: everything apply_move reports, plus the board as it
+// would be afterwards (with outcome.next_to_move to move).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Preview<P = VecPits> {
+    pub outcome: MoveOutcome,
+    pub board: Board<P>,
+}
+
+// the end-of-game sweep: when the side to move has no beans left to sow, the beans still in
+// play are moved into a pot.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Sweep {
+    // the side that had no beans left to sow
+    pub empty_side: Side,
+    // whose holes the remaining beans were taken from, and whose pot they went into
+    pub from_side: Side,
+    pub pot_owner: Side,
+    pub beans: i32,
+}
+
+// what Board::make_move changed, so that Board::unmake_move can put the board back exactly as
+// it was. The sowing itself is undone by walking the same path back, so only the beans that
+// were picked up need keeping. A Kalah capture empties two pits whose beans the outcome already
+// tells; an Oware capture or the final sweep empties a row of holes, which is kept in the
+// board's own storage (so a CompactBoard's Undo never allocates).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Undo<P: Pits = VecPits> {
+    side: Side,
+    hole: i32,
+    direction: Direction,
+    beans_sown: i32,
+    // the opponent's holes just before an Oware capture emptied some of them
+    captured_from: Option<P::Holes>,
+    // the swept side's holes just before the sweep, if the move ended the game
+    swept: Option<P::Holes>,
+    pub outcome: MoveOutcome,
+}
+
+impl<P: Pits> Copy for Undo<P> where P::Holes: Copy {}
+
+// a board's bean counts are kept in P (see pits.rs). Everything is written against Board, which
+// can hold any number of holes and beans; CompactBoard holds up to MAX_INLINE_HOLES holes a side
+// and u16::MAX beans in all, and is Copy. Both play by the same code below.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Board<P = VecPits> {
+    pits: P,
+    num_holes: i32,
+    rules: Rules,
+    // zobrist hash of the pits above, kept up to date by every mutation.
+    hash: u64,
+    // the hash of the mirrored board, kept up to date alongside, so that canonical_key is as
+    // cheap as position_key.
+    mirror_hash: u64,
+}
+
+pub type CompactBoard = Board<InlinePits>;
+
+// whether sowing in the given direction goes along side's holes from hole 1 up (rather than from
+// hole n down).
+fn sows_upwards(side: Side, direction: Direction) -> bool {
+    (side == Side::South) == (direction == Direction::CounterClockwise)
+}
+
+impl Board {
+    pub fn new(n_holes: i32, num_initial_beans_per_hole: i32) -> Self {
+        Board::new_with_rules(n_holes, num_initial_beans_per_hole, Rules::default())
+    }
+
+    // panics if there are no holes or the number of beans is negative; see from_contents for a
+    // constructor that checks its input.
+    pub fn new_with_rules(n_holes: i32, num_initial_beans_per_hole: i32, rules: Rules) -> Self {
+        assert!(
+            n_holes > 0,
+            "a board needs at least one hole, not {}",
+            n_holes
+        );
+        assert!(
+            num_initial_beans_per_hole >= 0,
+            "a hole cannot hold {} beans",
+            num_initial_beans_per_hole
+        );
+
+        let mut pits = VecPits::with_holes(n_holes).unwrap();
+        for s in [Side::North, Side::South] {
+            for hole in 1..=n_holes {
+                pits.set(s, hole, num_initial_beans_per_hole);
+            }
+        }
+        let mut b = Board {
+            pits,
+            num_holes: n_holes,
+            rules,
+            hash: 0,
+            mirror_hash: 0,
         };
+        b.hash = b.compute_hash();
+        b.mirror_hash = b.compute_mirror_hash();
+        b
+    }
 
-        let mut acc = 0;
-        for hole in v {
-            acc += hole;
+    // the pit after (side, hole) going round the board in the given direction, passing through
+    // both pots. Counter-clockwise, that is South's holes from 1 up, South's pot, North's holes
+    // from n down, North's pot and back to South's hole 1; clockwise is the same backwards.
+    pub fn get_next_location(
+        side: Side,
+        hole: i32,
+        direction: Direction,
+        num_holes: i32,
+    ) -> (Side, i32) {
+        assert!(hole >= 0 && hole <= num_holes);
+        let first_hole = |s: Side| {
+            if sows_upwards(s, direction) {
+                1
+            } else {
+                num_holes
+            }
+        };
+        if hole == 0 {
+            // from a pot the sowing goes on to the first hole of the side the pot leads into.
+            return match direction {
+                Direction::CounterClockwise => (side.opponent(), first_hole(side.opponent())),
+                Direction::Clockwise => (side, first_hole(side)),
+            };
+        }
+        let last_hole = num_holes + 1 - first_hole(side);
+        if hole == last_hole {
+            // the end of a side leads into its own pot counter-clockwise, and into the opponent's
+            // pot clockwise.
+            match direction {
+                Direction::CounterClockwise => (side, 0),
+                Direction::Clockwise => (side.opponent(), 0),
+            }
+        } else if sows_upwards(side, direction) {
+            (side, hole + 1)
+        } else {
+            (side, hole - 1)
         }
-        acc
     }
-    pub fn total_beans(&self) -> i32 {
-        let mut acc = 0;
-        for hole in self.north_holes.iter() {
-            acc += hole;
+}
+
+impl Board {
+    // builds a board from the contents of each side's holes (hole 1 first) and pots, checking
+    // that both sides have the same number of holes, at least one, and no count is negative.
+    pub fn from_contents(
+        north: &[i32],
+        south: &[i32],
+        north_pot: i32,
+        south_pot: i32,
+        rules: Rules,
+    ) -> Result<Self, BoardError> {
+        if north.len() != south.len() {
+            return Err(BoardError::SidesDiffer {
+                north: north.len(),
+                south: south.len(),
+            });
         }
-        for hole in self.south_holes.iter() {
-            acc += hole;
+        if north.is_empty() {
+            return Err(BoardError::BadHoleCount(0));
         }
-        acc += self.north_pot + self.south_pot;
-        acc
+
+        let mut b = Board::new_with_rules(north.len() as i32, 0, rules);
+        for hole in 1..=b.num_holes {
+            b.set_beans(Side::North, hole, north[(hole - 1) as usize])?;
+            b.set_beans(Side::South, hole, south[(hole - 1) as usize])?;
+        }
+        b.set_beans(Side::North, 0, north_pot)?;
+        b.set_beans(Side::South, 0, south_pot)?;
+        Ok(b)
+    }
+}
+
+impl TryFrom<&Board> for CompactBoard {
+    type Error = BoardError;
+
+    fn try_from(b: &Board) -> Result<Self, Self::Error> {
+        b.convert()
+    }
+}
+
+impl From<CompactBoard> for Board {
+    fn from(b: CompactBoard) -> Self {
+        b.convert().expect("any board fits in a Board")
     }
-    pub fn move_to_pot(&mut self, s: Side, hole: i32, pot_owner: Side) -> bool {
-        if hole <= 0 || hole > self.num_holes {
-            return false;
+}
+
+impl<P: Pits> Board<P> {
+    // the same board with its beans kept in Q instead. Fails if Q cannot hold this many holes,
+    // or as many beans as are on the board (which is the most any one pit could ever get).
+    fn convert<Q: Pits>(&self) -> Result<Board<Q>, BoardError> {
+        if self.total_beans() > Q::MAX_BEANS {
+            return Err(BoardError::TooLarge);
         }
-        let num_beans_removed: i32;
-        let hole_index = (hole - 1) as usize;
-        match s {
-            Side::North => {
-                num_beans_removed = self.north_holes[hole_index];
-                self.north_holes[hole_index] = 0;
+        let mut pits = Q::with_holes(self.num_holes).ok_or(BoardError::TooLarge)?;
+        for s in [Side::North, Side::South] {
+            for hole in 0..=self.num_holes {
+                pits.set(s, hole, self.beans(s, hole));
             }
-            Side::South => {
-                num_beans_removed = self.south_holes[hole_index];
-                self.south_holes[hole_index] = 0;
+        }
+        Ok(Board {
+            pits,
+            num_holes: self.num_holes,
+            rules: self.rules,
+            hash: self.hash,
+            mirror_hash: self.mirror_hash,
+        })
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    // the zobrist hash of the bean counts, independent of whose turn it is.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    // the zobrist hash of this board with side s to move, under its rules and with its number of
+    // holes (an empty pit adds nothing to the hash, so without it a board with more holes can
+    // hash the same).
+    pub fn position_key(&self, s: Side) -> u64 {
+        let key = self.hash ^ self.setup_key();
+        match s {
+            Side::North => key ^ zobrist::NORTH_TO_MOVE,
+            Side::South => key,
+        }
+    }
+
+    // recomputes the hash from scratch; hash() should always agree with this.
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = 0;
+        for s in [Side::North, Side::South] {
+            for hole in 0..=self.num_holes {
+                hash ^= zobrist::pit_key(s, hole, self.beans(s, hole));
             }
         }
-        match pot_owner {
-            Side::North => {
-                self.north_pot += num_beans_removed;
+        hash
+    }
+
+    fn compute_mirror_hash(&self) -> u64 {
+        let mut hash = 0;
+        for s in [Side::North, Side::South] {
+            for hole in 0..=self.num_holes {
+                let (ms, mhole) = self.mirror_pit(s, hole);
+                hash ^= zobrist::pit_key(ms, mhole, self.beans(s, hole));
             }
-            Side::South => {
-                self.south_pot += num_beans_removed;
+        }
+        hash
+    }
+
+    // where side s's hole (or pot) ends up on the mirrored board. Going either way round, the two
+    // sides' holes are sown in opposite orders (counter-clockwise, South's from hole 1 up and
+    // North's from hole n down), so hole k on one side plays the part of hole n + 1 - k on the
+    // other; holes opposite each other stay so.
+    pub fn mirror_pit(&self, s: Side, hole: i32) -> (Side, i32) {
+        if hole == 0 {
+            (s.opponent(), 0)
+        } else {
+            (s.opponent(), self.num_holes + 1 - hole)
+        }
+    }
+
+    // the board with North and South swapped, holes, pots and all. A position with North to
+    // move plays exactly like its mirror with South to move, with North's hole k becoming
+    // South's hole n + 1 - k.
+    pub fn mirrored(&self) -> Board<P> {
+        let mut pits = self.pits.clone();
+        for s in [Side::North, Side::South] {
+            for hole in 0..=self.num_holes {
+                let (ms, mhole) = self.mirror_pit(s, hole);
+                pits.set(ms, mhole, self.beans(s, hole));
             }
         }
+        Board {
+            pits,
+            num_holes: self.num_holes,
+            rules: self.rules,
+            hash: self.mirror_hash,
+            mirror_hash: self.hash,
+        }
+    }
+
+    // a key for the position with side s to move that is the same for the position and its
+    // mirror with the other side to move: the position_key of whichever of the two has South to
+    // move.
+    pub fn canonical_key(&self, s: Side) -> u64 {
+        match s {
+            Side::South => self.position_key(Side::South),
+            Side::North => self.mirror_hash ^ self.setup_key(),
+        }
+    }
+
+    // what position_key mixes in besides the bean counts.
+    fn setup_key(&self) -> u64 {
+        zobrist::rules_key(&self.rules) ^ zobrist::size_key(self.num_holes)
+    }
 
-        true
+    pub fn holes(&self) -> i32 {
+        self.num_holes
+    }
+    // the beans in side s's hole (hole 0 being the pot). Panics if there is no such hole; see
+    // try_beans.
+    pub fn beans(&self, s: Side, hole: i32) -> i32 {
+        assert!(
+            hole >= 0 && hole <= self.num_holes,
+            "there is no hole {}",
+            hole
+        );
+        self.pits.get(s, hole)
     }
-    pub fn set_beans(&mut self, s: Side, hole: i32, beans: i32) -> bool {
+    pub fn try_beans(&self, s: Side, hole: i32) -> Result<i32, BoardError> {
+        self.check_hole(hole)?;
+        Ok(self.beans(s, hole))
+    }
+
+    // checks that hole is a hole or pot (hole 0) on this board.
+    fn check_hole(&self, hole: i32) -> Result<(), BoardError> {
         if hole < 0 || hole > self.num_holes {
-            return false;
+            return Err(BoardError::NoSuchHole(hole));
         }
-        if beans < 0 {
-            return false;
+        Ok(())
+    }
+
+    pub fn beans_in_play(&self, s: Side) -> i32 {
+        let mut acc = 0;
+        for hole in 1..=self.num_holes {
+            acc += self.pits.get(s, hole);
         }
-        if hole == 0 {
-            match s {
-                Side::North => {
-                    self.north_pot = beans;
-                }
-                Side::South => {
-                    self.south_pot = beans;
-                }
-            }
-        } else {
-            match s {
-                Side::North => {
-                    self.north_holes[(hole - 1) as usize] = beans;
+        acc
+    }
+    pub fn total_beans(&self) -> i32 {
+        let mut acc = 0;
+        for s in [Side::North, Side::South] {
+            acc += self.beans_in_play(s) + self.pits.get(s, 0);
+        }
+        acc
+    }
+    // checks that no pit holds a negative number of beans or more than the storage can hold, and
+    // that the beans add up to expected_total, the number the board was set up with (no move
+    // ever changes it).
+    pub fn validate(&self, expected_total: i32) -> Result<(), BoardError> {
+        for s in [Side::North, Side::South] {
+            for hole in 0..=self.num_holes {
+                let beans = self.beans(s, hole);
+                if beans < 0 {
+                    return Err(BoardError::NegativeBeans(beans));
                 }
-                Side::South => {
-                    self.south_holes[(hole - 1) as usize] = beans;
+                if beans > P::MAX_BEANS {
+                    return Err(BoardError::TooLarge);
                 }
             }
         }
 
-        true
+        let found = self.total_beans();
+        if found != expected_total {
+            return Err(BoardError::WrongTotal {
+                expected: expected_total,
+                found,
+            });
+        }
+        Ok(())
     }
 
-    pub fn sow(&mut self, side: Side, hole: i32, end_side: &mut Side, end_hole: &mut i32) -> bool {
-        if hole <= 0 || hole > self.num_holes {
-            return false;
+    // in debug builds, the number of beans on the board, for debug_check to compare with after an
+    // operation that must not change it.
+    fn debug_total(&self) -> i32 {
+        if cfg!(debug_assertions) {
+            self.total_beans()
+        } else {
+            0
         }
+    }
 
-        let player_sowing: Side = side;
+    // in debug builds, panics if the board is not valid with expected_total beans or its hash is
+    // out of date, so that a bug in the rules shows up where it happens.
+    fn debug_check(&self, expected_total: i32) {
+        if cfg!(debug_assertions) {
+            if let Err(e) = self.validate(expected_total) {
+                panic!("broken board: {} in {:?}", e, self);
+            }
+            assert_eq!(self.hash, self.compute_hash(), "stale hash in {:?}", self);
+            assert_eq!(
+                self.mirror_hash,
+                self.compute_mirror_hash(),
+                "stale mirror hash in {:?}",
+                self
+            );
+        }
+    }
 
-        let mut num_beans_in_hand = self.beans(side, hole);
-        self.set_beans(side, hole, 0);
+    // moves the beans in side s's hole into pot_owner's pot, and returns how many there were.
+    pub fn move_to_pot(&mut self, s: Side, hole: i32, pot_owner: Side) -> Result<i32, BoardError> {
+        if hole == 0 {
+            return Err(BoardError::NoSuchHole(hole));
+        }
+        self.check_hole(hole)?;
+        let total = self.debug_total();
+        let moved = self.empty_into_pot(s, hole, pot_owner);
+        self.debug_check(total);
+        Ok(moved)
+    }
 
-        let mut side = side;
-        let mut hole = hole;
+    // move_to_pot for a hole known to be on the board.
+    fn empty_into_pot(&mut self, s: Side, hole: i32, pot_owner: Side) -> i32 {
+        let num_beans_removed = self.beans(s, hole);
+        self.set_pit(s, hole, 0);
+        self.set_pit(pot_owner, 0, self.beans(pot_owner, 0) + num_beans_removed);
+        num_beans_removed
+    }
 
-        while num_beans_in_hand > 0 {
-            (side, hole) = Board::get_next_location(side, hole, player_sowing, self.num_holes);
-            self.set_beans(side, hole, self.beans(side, hole) + 1);
-            num_beans_in_hand -= 1;
+    pub fn set_beans(&mut self, s: Side, hole: i32, beans: i32) -> Result<(), BoardError> {
+        self.check_hole(hole)?;
+        if beans < 0 {
+            return Err(BoardError::NegativeBeans(beans));
         }
+        let total = self.total_beans() - self.beans(s, hole);
+        if total > P::MAX_BEANS - beans {
+            return Err(BoardError::TooLarge);
+        }
+        self.set_pit(s, hole, beans);
+        self.debug_check(total + beans);
+        Ok(())
+    }
+
+    // set_beans for a hole known to be on the board, keeping the hash up to date.
+    fn set_pit(&mut self, s: Side, hole: i32, beans: i32) {
+        let old = self.beans(s, hole);
+        self.hash ^= zobrist::pit_key(s, hole, old) ^ zobrist::pit_key(s, hole, beans);
+        let (ms, mhole) = self.mirror_pit(s, hole);
+        self.mirror_hash ^= zobrist::pit_key(ms, mhole, old) ^ zobrist::pit_key(ms, mhole, beans);
+        self.pits.set(s, hole, beans);
+    }
 
-        *end_side = side;
-        *end_hole = hole;
+    // picks up the beans in side's hole and sows them one at a time along the sowing path, with
+    // no capture and no check that the hole has beans. Returns where the last bean went (the hole
+    // itself if it was empty).
+    pub fn sow(&mut self, side: Side, mv: Move) -> Result<(Side, i32), BoardError> {
+        let hole = mv.hole();
+        self.check_hole(hole)?;
+        let direction = self.direction_of(mv)?;
+        let total = self.debug_total();
 
-        true
+        let num_beans_in_hand = self.beans(side, hole);
+        self.set_pit(side, hole, 0);
+        let end = self.sow_path(side, hole, num_beans_in_hand, direction, 1);
+        self.debug_check(total);
+        Ok(end)
     }
 
-    pub fn get_next_location(
+    // adds `step` beans (1 to sow, -1 to take a sowing back) to each pit that `beans` beans sown
+    // by side from its hole in the given direction pass through, and returns where the last one
+    // goes. Every full lap of the board adds the same to each pit on it, so the laps are added
+    // all at once and only what is left over is walked bean by bean; the cost does not grow with
+    // the number of beans.
+    fn sow_path(
+        &mut self,
         side: Side,
         hole: i32,
-        player_sowing: Side,
-        num_holes: i32,
+        beans: i32,
+        direction: Direction,
+        step: i32,
     ) -> (Side, i32) {
-        assert!(hole >= 0 && hole <= num_holes);
-        if hole == 0 {
-            // if the hole is zero, that means we are at a players pot.
-            // we know it is only possible if the player sowing is the same as the pot's side.
-            match side {
-                Side::North => return (Side::South, 1),
-                Side::South => return (Side::North, num_holes),
-            }
-        }
-        match side {
-            Side::North => {
-                if hole == 1 {
-                    match player_sowing {
-                        Side::North => (Side::North, 0),
-                        Side::South => (Side::South, 1),
+        if beans == 0 {
+            return (side, hole);
+        }
+
+        let player_sowing = side;
+        let origin = (side, hole);
+        let skip_origin = self.rules.skips_origin();
+        let (laps, mut remainder) = self.laps(side, beans);
+
+        if laps > 0 {
+            for s in [Side::North, Side::South] {
+                for h in 1..=self.num_holes {
+                    if !(skip_origin && (s, h) == origin) {
+                        self.set_pit(s, h, self.beans(s, h) + step * laps);
                     }
-                } else {
-                    (Side::North, hole - 1)
                 }
             }
-            Side::South => {
-                if hole == num_holes {
-                    match player_sowing {
-                        Side::North => (Side::North, num_holes),
-                        Side::South => (Side::South, 0),
-                    }
-                } else {
-                    (Side::South, hole + 1)
+            for pot_owner in [Side::North, Side::South] {
+                if self.rules.sows_into_pot(pot_owner, player_sowing) {
+                    let pot = self.beans(pot_owner, 0);
+                    self.set_pit(pot_owner, 0, pot + step * laps);
                 }
             }
         }
+
+        let (mut side, mut hole) = origin;
+        while remainder > 0 {
+            (side, hole) = self.next_location(side, hole, player_sowing, direction);
+            if skip_origin && (side, hole) == origin {
+                continue;
+            }
+            self.set_pit(side, hole, self.beans(side, hole) + step);
+            remainder -= 1;
+        }
+
+        (side, hole)
     }
-}
 
-#[cfg(test)]
-mod tests {
+    // how `beans` beans sown by side go round the board: the full laps made before the last
+    // lap, and the beans sown on the last lap (full or not), so that walking them ends where the
+    // last bean goes.
+    fn laps(&self, side: Side, beans: i32) -> (i32, i32) {
+        let pots_sown = [Side::North, Side::South]
+            .into_iter()
+            .filter(|&pot_owner| self.rules.sows_into_pot(pot_owner, side))
+            .count() as i32;
+        // the pits on one lap: both sides' holes, the pots that are sown into, and not the
+        // origin if it is skipped.
+        let lap = 2 * self.num_holes + pots_sown - self.rules.skips_origin() as i32;
+        let laps = beans / lap;
+        match beans % lap {
+            0 => (laps - 1, lap),
+            remainder => (laps, remainder),
+        }
+    }
 
-    use super::*;
-    use Side::*;
+    // a quick guess at what legal move mv by side s would do, for putting moves in a good order
+    // before searching them, without sowing: whether it earns another turn (which is exact) and
+    // about how many beans it captures (beans dropped by the sowing itself on the way to the
+    // captured holes, and all but the last hole of an Oware capture, are left out).
+    pub fn guess_move(&self, s: Side, mv: Move) -> (bool, i32) {
+        let Ok(direction) = self.direction_of(mv) else {
+            return (false, 0);
+        };
+        let origin = (s, mv.hole());
+        let (laps, remainder) = self.laps(s, self.beans(s, mv.hole()));
+        let (mut end_side, mut end_hole) = origin;
+        let mut left = remainder;
+        while left > 0 {
+            (end_side, end_hole) = self.next_location(end_side, end_hole, s, direction);
+            if !(self.rules.skips_origin() && (end_side, end_hole) == origin) {
+                left -= 1;
+            }
+        }
+        if end_hole == 0 {
+            return (end_side == s, 0);
+        }
 
-    #[test]
-    fn sow_beans() {
-        let mut b: Board = Board::new(4, 4);
+        // the beans in the last hole once it is sown: what it held (nothing, if the sowing
+        // started there), one from each full lap and the last bean.
+        let held = if (end_side, end_hole) == origin {
+            0
+        } else {
+            self.beans(end_side, end_hole)
+        };
+        let landed = held + laps + 1;
+        let captured = match self.rules.ruleset {
+            Ruleset::Kalah if end_side == s && landed == 1 => {
+                let opposite = self.beans(s.opponent(), end_hole);
+                match self.rules.capture {
+                    CaptureRule::Standard if opposite > 0 => 1 + opposite,
+                    CaptureRule::EvenIfOppositeEmpty => 1 + opposite,
+                    _ => 0,
+                }
+            }
+            Ruleset::Oware if end_side != s && (2..=3).contains(&landed) => landed,
+            _ => 0,
+        };
+        (false, captured)
+    }
 
-        let mut end_side = North;
-        let mut end_hole = -1;
-        assert!(!b.sow(South, 0, &mut end_side, &mut end_hole));
-        assert!(b.sow(South, 1, &mut end_side, &mut end_hole));
-        assert!(end_hole == 0 && end_side == South);
-        assert!(b.beans(South, 0) == 1);
-        assert!(b.beans(South, 1) == 0);
-        assert!(b.beans(South, 2) == 5);
-        assert!(b.beans(South, 3) == 5);
-        assert!(b.beans(South, 4) == 5);
+    // the move sowing the given hole the way the rules sow, if side s may make it; see
+    // check_sowing.
+    pub fn check_move(&self, s: Side, hole: i32) -> Result<Move, BoardError> {
+        self.check_sowing(s, Move::new(hole)?)
+    }
 
-        assert!(b.beans_in_play(North) == 16);
-        assert!(b.beans_in_play(South) == 15);
-        assert!(b.total_beans() == 32);
+    // mv, if side s may make it: the hole must hold beans, the move must go a way the rules allow
+    // (and say which if the rules leave it to the player), and under rules where players must
+    // feed an opponent with no beans, the sowing must reach the opponent's side.
+    pub fn check_sowing(&self, s: Side, mv: Move) -> Result<Move, BoardError> {
+        let hole = mv.hole();
+        if hole > self.num_holes {
+            return Err(BoardError::NoSuchHole(hole));
+        }
+        if self.beans(s, hole) == 0 {
+            return Err(BoardError::EmptyHole(hole));
+        }
+        let direction = self.direction_of(mv)?;
+        if self.rules.must_feed()
+            && self.beans_in_play(s.opponent()) == 0
+            && self.beans(s, hole) < self.distance_to_opponent(s, hole, direction)
+        {
+            return Err(BoardError::MustFeed(hole));
+        }
+        Ok(mv)
     }
 
-    #[test]
-    fn sow_with_many_beans_per_pot() {
-        let mut b: Board = Board::new(2, 61);
-        let mut end_side = North;
-        let mut end_hole = -1;
-        assert!(b.sow(South, 1, &mut end_side, &mut end_hole));
+    // the way mv is sown under the rules.
+    fn direction_of(&self, mv: Move) -> Result<Direction, BoardError> {
+        match (self.rules.sowing, mv.direction()) {
+            (SowingRule::Always(direction), None) => Ok(direction),
+            (SowingRule::Always(direction), Some(asked)) if asked == direction => Ok(direction),
+            (SowingRule::Always(_), Some(_)) => Err(BoardError::WrongDirection(mv.hole())),
+            (SowingRule::PlayersChoice, Some(direction)) => Ok(direction),
+            (SowingRule::PlayersChoice, None) => Err(BoardError::NoDirection(mv.hole())),
+        }
+    }
 
-        assert!(end_side == South && end_hole == 2);
+    pub fn is_legal_move(&self, s: Side, hole: i32) -> bool {
+        self.check_move(s, hole).is_ok()
+    }
 
-        assert_eq!(b.beans(South, 0), 12);
-        assert_eq!(b.beans(South, 1), 12);
-        assert_eq!(b.beans(South, 2), 74);
-        assert_eq!(b.beans(North, 0), 0);
-        assert_eq!(b.beans(North, 1), 73);
-        assert_eq!(b.beans(North, 2), 73);
-        assert_eq!(b.beans_in_play(South), 86);
-        assert_eq!(b.beans_in_play(North), 146);
-        assert_eq!(b.total_beans(), 244);
+    // the moves side s may make, lowest hole first (and counter-clockwise before clockwise where
+    // the player chooses), which is also the order of Move's Ord.
+    pub fn legal_moves(&self, s: Side) -> impl Iterator<Item = Move> + '_ {
+        let directions: &[Option<Direction>] = match self.rules.sowing {
+            SowingRule::Always(_) => &[None],
+            SowingRule::PlayersChoice => &[
+                Some(Direction::CounterClockwise),
+                Some(Direction::Clockwise),
+            ],
+        };
+        (1..=self.num_holes)
+            .flat_map(move |hole| {
+                directions
+                    .iter()
+                    .map(move |&direction| Move { hole, direction })
+            })
+            .filter(move |&mv| self.check_sowing(s, mv).is_ok())
+    }
+
+    // what side s playing mv would do, leaving this board alone.
+    pub fn preview(&self, s: Side, mv: Move) -> Result<Preview<P>, BoardError> {
+        let mut board = self.clone();
+        let outcome = board.apply_move(s, mv)?;
+        Ok(Preview { outcome, board })
+    }
+
+    // how many beans sown from side s's hole in the given direction it takes to reach one of the
+    // opponent's holes.
+    fn distance_to_opponent(&self, s: Side, hole: i32, direction: Direction) -> i32 {
+        let (mut side, mut hole) = (s, hole);
+        let mut distance = 0;
+        while side == s || hole == 0 {
+            (side, hole) = self.next_location(side, hole, s, direction);
+            distance += 1;
+        }
+        distance
+    }
+
+    // the game is over once the side to move has no legal move: normally because it has no
+    // beans left to sow, but under Oware also because it cannot give its empty opponent any.
+    pub fn is_game_over(&self, side_to_move: Side) -> bool {
+        self.legal_moves(side_to_move).next().is_none()
+    }
+
+    // makes one move for side s: sows the beans in the given hole, then makes any capture, and
+    // ends the game with a sweep if the side to move next has no legal move. This is the only
+    // place the rules of a move live; the game and every player searching ahead go through it.
+    // Returns an error (leaving the board unchanged) if the move is not legal.
+    pub fn apply_move(&mut self, s: Side, mv: Move) -> Result<MoveOutcome, BoardError> {
+        self.make_move(s, mv).map(|undo| undo.outcome)
+    }
+
+    // apply_move, returning what unmake_move needs to take the move back.
+    pub fn make_move(&mut self, s: Side, mv: Move) -> Result<Undo<P>, BoardError> {
+        let hole = self.check_sowing(s, mv)?.hole();
+        let direction = self.direction_of(mv)?;
+        let total = self.debug_total();
+
+        let beans_sown = self.beans(s, hole);
+        let (end_side, end_hole) = self.sow(s, mv)?;
+
+        let mut captured_from = None;
+        let captured = match self.rules.ruleset {
+            Ruleset::Kalah => self.kalah_capture(s, end_side, end_hole),
+            Ruleset::Oware => {
+                self.oware_capture(s, end_side, end_hole, direction, &mut captured_from)
+            }
+        };
+
+        // a move that ends in the mover's own pot earns another turn.
+        let extra_turn = end_hole == 0 && end_side == s;
+        let next_to_move = if extra_turn { s } else { s.opponent() };
+
+        let mut swept = None;
+        let sweep = if self.is_game_over(next_to_move) {
+            let from_side = self.empty_side(next_to_move).opponent();
+            swept = Some(self.pits.holes(from_side));
+            Some(self.end_game(next_to_move))
+        } else {
+            None
+        };
+        self.debug_check(total);
+
+        Ok(Undo {
+            side: s,
+            hole,
+            direction,
+            beans_sown,
+            captured_from,
+            swept,
+            outcome: MoveOutcome {
+                end_side,
+                end_hole,
+                captured,
+                extra_turn,
+                next_to_move,
+                sweep,
+            },
+        })
+    }
+
+    // takes back the move undo was returned for, which must be the last move made on this board.
+    pub fn unmake_move(&mut self, undo: &Undo<P>) {
+        let total = self.debug_total();
+        if let (Some(sweep), Some(swept)) = (undo.outcome.sweep, &undo.swept) {
+            self.restore_holes(sweep.from_side, swept);
+            let pot = self.beans(sweep.pot_owner, 0);
+            self.set_pit(sweep.pot_owner, 0, pot - sweep.beans);
+        }
+
+        if let Some(captured_from) = &undo.captured_from {
+            self.restore_holes(undo.side.opponent(), captured_from);
+        } else if undo.outcome.captured > 0 {
+            // a Kalah capture: the capturing bean and the beans opposite it.
+            let (s, hole) = (undo.side, undo.outcome.end_hole);
+            self.set_pit(s, hole, 1);
+            self.set_pit(s.opponent(), hole, undo.outcome.captured - 1);
+        }
+        let pot = self.beans(undo.side, 0);
+        self.set_pit(undo.side, 0, pot - undo.outcome.captured);
+
+        // go along the sowing path again, taking a bean back from every pit it passes.
+        self.sow_path(undo.side, undo.hole, undo.beans_sown, undo.direction, -1);
+        self.set_pit(undo.side, undo.hole, undo.beans_sown);
+        self.debug_check(total);
+    }
+
+    // puts side's holes back as holes (from Pits::holes) says they were.
+    fn restore_holes(&mut self, side: Side, holes: &P::Holes) {
+        for h in 1..=self.num_holes {
+            self.set_pit(side, h, P::saved_beans(holes, h));
+        }
+    }
+
+    // makes the Kalah capture, if any, for a move by s that ended at (end_side, end_hole), and
+    // returns the beans it moved into s's pot.
+    fn kalah_capture(&mut self, s: Side, end_side: Side, end_hole: i32) -> i32 {
+        // execute a capture if the move ends in a player's own empty hole, and the opponent's hole
+        // opposite the ending hole is nonempty (or, depending on the rules, even if it is empty):
+        #[allow(clippy::collapsible_if)]
+        if end_hole != 0 && end_side == s {
+            if self.beans(s, end_hole) == 1 {
+                // if the hole used to be empty
+                let opposite_beans = self.beans(s.opponent(), end_hole);
+                let capture = match self.rules.capture {
+                    CaptureRule::Standard => opposite_beans != 0,
+                    CaptureRule::EvenIfOppositeEmpty => true,
+                    CaptureRule::NoCapture => false,
+                };
+                if capture {
+                    self.empty_into_pot(s, end_hole, s);
+                    self.empty_into_pot(s.opponent(), end_hole, s);
+                    return 1 + opposite_beans;
+                }
+            }
+        }
+        0
+    }
+
+    // makes the Oware capture, if any, for a move by s sown in the given direction that ended at
+    // (end_side, end_hole), and returns the beans it moved into s's pot. If it captures, the
+    // opponent's holes as they were before are saved in captured_from.
+    fn oware_capture(
+        &mut self,
+        s: Side,
+        end_side: Side,
+        end_hole: i32,
+        direction: Direction,
+        captured_from: &mut Option<P::Holes>,
+    ) -> i32 {
+        let opponent = s.opponent();
+        if end_side != opponent {
+            return 0;
+        }
+
+        // walk back along the sowing path over the opponent's holes holding 2 or 3, which run
+        // from end_hole to just short of hole.
+        let back = if sows_upwards(opponent, direction) {
+            -1
+        } else {
+            1
+        };
+        let mut total = 0;
+        let mut hole = end_hole;
+        while hole >= 1 && hole <= self.num_holes && (2..=3).contains(&self.beans(opponent, hole)) {
+            total += self.beans(opponent, hole);
+            hole += back;
+        }
+
+        if total == 0 || total == self.beans_in_play(opponent) {
+            // nothing to take, or a grand slam: taking everything is not allowed, so nothing is
+            // taken.
+            return 0;
+        }
+        *captured_from = Some(self.pits.holes(opponent));
+        let mut h = end_hole;
+        while h != hole {
+            self.empty_into_pot(opponent, h, s);
+            h += back;
+        }
+        total
+    }
+
+    // plays `plies` random legal moves (single sowings, so an extra turn uses up a ply) starting
+    // with side_to_move, stopping early if the game ends. Returns the side to move afterwards.
+    // Useful for giving a series of games varied, reproducible openings.
+    pub fn play_random_opening(&mut self, side_to_move: Side, plies: i32, rng: &mut Rng) -> Side {
+        let mut side_to_move = side_to_move;
+        for _ in 0..plies {
+            let legal: Vec<Move> = self.legal_moves(side_to_move).collect();
+            if legal.is_empty() {
+                break;
+            }
+            let mv = legal[rng.range(0, legal.len() as i32 - 1) as usize];
+            let outcome = self.apply_move(side_to_move, mv).unwrap();
+            side_to_move = outcome.next_to_move;
+            if outcome.game_over() {
+                break;
+            }
+        }
+        side_to_move
+    }
+
+    // ends the game when side_to_move has no legal move, sweeping the beans left in play. The
+    // side that ran out is side_to_move itself, except under Oware when it is its opponent that
+    // has no beans and side_to_move cannot give it any.
+    pub fn end_game(&mut self, side_to_move: Side) -> Sweep {
+        self.sweep(self.empty_side(side_to_move))
+    }
+
+    // the side end_game treats as having run out of beans.
+    fn empty_side(&self, side_to_move: Side) -> Side {
+        if self.beans_in_play(side_to_move) == 0 {
+            side_to_move
+        } else {
+            side_to_move.opponent()
+        }
+    }
+
+    // ends the game for empty_side, which has no beans left to sow: the beans remaining in its
+    // opponent's holes are swept into a pot chosen by the rules (normally the opponent's).
+    pub fn sweep(&mut self, empty_side: Side) -> Sweep {
+        let from_side = empty_side.opponent();
+        let pot_owner = match self.rules.sweep {
+            SweepRule::ToOwner => from_side,
+            SweepRule::ToEmptySide => empty_side,
+        };
+        let total = self.debug_total();
+        let beans = self.beans_in_play(from_side);
+        for hole in 1..=self.num_holes {
+            self.empty_into_pot(from_side, hole, pot_owner);
+        }
+        self.debug_check(total);
+
+        Sweep {
+            empty_side,
+            from_side,
+            pot_owner,
+            beans,
+        }
+    }
+
+    // the place the next bean sown by player_sowing in the given direction goes after
+    // (side, hole), taking the rules into account: get_next_location, but skipping the pots that
+    // are not sown into.
+    fn next_location(
+        &self,
+        side: Side,
+        hole: i32,
+        player_sowing: Side,
+        direction: Direction,
+    ) -> (Side, i32) {
+        let (side, hole) = Board::get_next_location(side, hole, direction, self.num_holes);
+        if hole == 0 && !self.rules.sows_into_pot(side, player_sowing) {
+            Board::get_next_location(side, hole, direction, self.num_holes)
+        } else {
+            (side, hole)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::rules::{CaptureRule, Direction, Rules, SowingRule, StoreRule, SweepRule};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use Side::*;
+
+    fn mv(hole: i32) -> Move {
+        Move::new(hole).unwrap()
+    }
+
+    #[test]
+    fn sow_beans() {
+        let mut b: Board = Board::new(4, 4);
+
+        assert_eq!(b.sow(South, mv(5)), Err(BoardError::NoSuchHole(5)));
+        let (end_side, end_hole) = b.sow(South, mv(1)).unwrap();
+        assert!(end_hole == 0 && end_side == South);
+        assert!(b.beans(South, 0) == 1);
+        assert!(b.beans(South, 1) == 0);
+        assert!(b.beans(South, 2) == 5);
+        assert!(b.beans(South, 3) == 5);
+        assert!(b.beans(South, 4) == 5);
+
+        assert!(b.beans_in_play(North) == 16);
+        assert!(b.beans_in_play(South) == 15);
+        assert!(b.total_beans() == 32);
+    }
+
+    #[test]
+    fn sow_with_many_beans_per_pot() {
+        let mut b: Board = Board::new(2, 61);
+        let (end_side, end_hole) = b.sow(South, mv(1)).unwrap();
+
+        assert!(end_side == South && end_hole == 2);
+
+        assert_eq!(b.beans(South, 0), 12);
+        assert_eq!(b.beans(South, 1), 12);
+        assert_eq!(b.beans(South, 2), 74);
+        assert_eq!(b.beans(North, 0), 0);
+        assert_eq!(b.beans(North, 1), 73);
+        assert_eq!(b.beans(North, 2), 73);
+        assert_eq!(b.beans_in_play(South), 86);
+        assert_eq!(b.beans_in_play(North), 146);
+        assert_eq!(b.total_beans(), 244);
+    }
+
+    // sowing as it was first written, one bean per step; sow must always agree with it.
+    fn sow_one_bean_at_a_time(
+        b: &mut Board,
+        side: Side,
+        hole: i32,
+        direction: Direction,
+    ) -> (Side, i32) {
+        let player_sowing = side;
+        let origin = (side, hole);
+        let mut num_beans_in_hand = b.beans(side, hole);
+        b.set_beans(side, hole, 0).unwrap();
+
+        let (mut side, mut hole) = origin;
+        while num_beans_in_hand > 0 {
+            (side, hole) = b.next_location(side, hole, player_sowing, direction);
+            if b.rules().skips_origin() && (side, hole) == origin {
+                continue;
+            }
+            b.set_beans(side, hole, b.beans(side, hole) + 1).unwrap();
+            num_beans_in_hand -= 1;
+        }
+        (side, hole)
+    }
+
+    #[test]
+    fn sowing_agrees_with_one_bean_at_a_time() {
+        let mut rng = Rng::new(14);
+        for _ in 0..3000 {
+            let rules = if rng.range(0, 1) == 0 {
+                Rules::kalah()
+            } else {
+                Rules::oware()
+            };
+            let direction =
+                [Direction::CounterClockwise, Direction::Clockwise][rng.range(0, 1) as usize];
+            let stores =
+                [StoreRule::Own, StoreRule::Both, StoreRule::Neither][rng.range(0, 2) as usize];
+            let rules = rules
+                .with_sowing(SowingRule::Always(direction))
+                .with_stores(stores);
+            let mut b = Board::new_with_rules(rng.range(1, 7), 0, rules);
+            let max_beans = [3, 30, 300][rng.range(0, 2) as usize];
+            for s in [North, South] {
+                for hole in 0..=b.holes() {
+                    b.set_beans(s, hole, rng.range(0, max_beans)).unwrap();
+                }
+            }
+            let side = if rng.range(0, 1) == 0 { North } else { South };
+            let hole = rng.range(1, b.holes());
+
+            let mut expected = b.clone();
+            let expected_end = sow_one_bean_at_a_time(&mut expected, side, hole, direction);
+            assert_eq!(b.sow(side, mv(hole)), Ok(expected_end));
+            assert_eq!(b, expected);
+        }
+    }
+
+    #[test]
+    fn guessed_moves_agree_with_the_moves_made() {
+        let mut rng = Rng::new(11);
+        for _ in 0..3000 {
+            let kalah = rng.range(0, 1) == 0;
+            let rules = if kalah {
+                Rules::kalah()
+            } else {
+                Rules::oware()
+            };
+            let direction =
+                [Direction::CounterClockwise, Direction::Clockwise][rng.range(0, 1) as usize];
+            let stores =
+                [StoreRule::Own, StoreRule::Both, StoreRule::Neither][rng.range(0, 2) as usize];
+            let rules = rules
+                .with_sowing(SowingRule::Always(direction))
+                .with_stores(stores);
+            let mut b = Board::new_with_rules(rng.range(1, 7), 0, rules);
+            let max_beans = [3, 30][rng.range(0, 1) as usize];
+            for s in [North, South] {
+                for hole in 0..=b.holes() {
+                    b.set_beans(s, hole, rng.range(0, max_beans)).unwrap();
+                }
+            }
+            let side = if rng.range(0, 1) == 0 { North } else { South };
+
+            for mv in b.legal_moves(side).collect::<Vec<Move>>() {
+                let (extra_turn, captured) = b.guess_move(side, mv);
+                let outcome = b.preview(side, mv).unwrap().outcome;
+                assert_eq!(extra_turn, outcome.extra_turn);
+                // a Kalah capture only gets bigger with beans sown opposite on the way.
+                if kalah && captured > 0 {
+                    assert!(outcome.captured >= captured);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sowing_exact_laps() {
+        // 13 beans from South's hole 1 on a 6-hole board go once round every pit but North's
+        // pot, ending back in the emptied hole; under Oware 12 beans skip the origin and end in
+        // the hole before it.
+        let mut b = Board::new(6, 0);
+        b.set_beans(South, 1, 13).unwrap();
+        assert_eq!(b.sow(South, mv(1)), Ok((South, 1)));
+        assert_eq!(contents(&b), (vec![1; 6], vec![1; 6], 0, 1));
+
+        let mut b = Board::new_with_rules(6, 0, Rules::oware());
+        b.set_beans(South, 1, 11).unwrap();
+        assert_eq!(b.sow(South, mv(1)), Ok((North, 1)));
+        b.set_beans(South, 1, 22).unwrap();
+        assert_eq!(b.sow(South, mv(1)), Ok((North, 1)));
+        assert_eq!(b.beans(South, 1), 0);
+        assert_eq!(b.beans(North, 1), 3);
+    }
+
+    #[test]
+    fn sowing_a_huge_hole() {
+        let mut b = Board::new(6, 0);
+        b.set_beans(North, 3, 1_000_000_000).unwrap();
+        let undo = b.make_move(North, mv(3)).unwrap();
+        assert_eq!(b.total_beans(), 1_000_000_000);
+        assert_eq!(b.hash(), b.compute_hash());
+        b.unmake_move(&undo);
+        assert_eq!(b.beans(North, 3), 1_000_000_000);
+        assert_eq!(b.beans_in_play(South), 0);
+    }
+
+    #[test]
+    fn hash_is_updated_incrementally() {
+        let mut b: Board = Board::new(6, 4);
+        let start = b.hash();
+
+        b.sow(South, mv(3)).unwrap();
+        assert_eq!(b.hash(), b.compute_hash());
+        assert_ne!(b.hash(), start);
+
+        assert_eq!(b.move_to_pot(North, 2, South), Ok(4));
+        assert_eq!(b.hash(), b.compute_hash());
+
+        b.set_beans(North, 0, 9).unwrap();
+        assert_eq!(b.hash(), b.compute_hash());
+    }
+
+    #[test]
+    fn transpositions_hash_equally() {
+        // the same beans reached in two different orders
+        let mut b1: Board = Board::new(6, 4);
+        let mut b2: Board = Board::new(6, 4);
+        b1.sow(South, mv(1)).unwrap();
+        b1.sow(North, mv(6)).unwrap();
+        b2.sow(North, mv(6)).unwrap();
+        b2.sow(South, mv(1)).unwrap();
+        assert_eq!(b1.hash(), b2.hash());
+
+        assert_ne!(b1.position_key(North), b1.position_key(South));
+        assert_ne!(b1.hash(), Board::new(6, 4).hash());
+    }
+
+    // builds a board from the contents of each side's holes (hole 1 first) and pots.
+    fn board_from(north: &[i32], south: &[i32], north_pot: i32, south_pot: i32) -> Board {
+        Board::from_contents(north, south, north_pot, south_pot, Rules::default()).unwrap()
+    }
+
+    fn contents(b: &Board) -> (Vec<i32>, Vec<i32>, i32, i32) {
+        let north = (1..=b.holes()).map(|hole| b.beans(North, hole)).collect();
+        let south = (1..=b.holes()).map(|hole| b.beans(South, hole)).collect();
+        (north, south, b.beans(North, 0), b.beans(South, 0))
+    }
+
+    #[test]
+    fn legal_moves_are_the_holes_with_beans() {
+        let b = board_from(&[0, 3, 0], &[1, 0, 2], 0, 0);
+        let holes = |s: Side| b.legal_moves(s).map(|m| m.hole()).collect::<Vec<i32>>();
+        assert_eq!(holes(South), vec![1, 3]);
+        assert_eq!(holes(North), vec![2]);
+
+        let empty = board_from(&[0, 0, 0], &[1, 0, 2], 0, 0);
+        assert_eq!(empty.legal_moves(North).count(), 0);
+        assert!(empty.is_game_over(North));
+    }
+
+    #[test]
+    fn legal_moves_follow_the_rules() {
+        let b = oware_board_from(&[0, 0, 0, 0, 0, 0], &[1, 0, 0, 0, 2, 1]);
+        let holes: Vec<i32> = b.legal_moves(South).map(|m| m.hole()).collect();
+        assert_eq!(holes, vec![5, 6]);
+    }
+
+    #[test]
+    fn preview_does_not_change_the_board() {
+        let b = board_from(&[0, 2, 5], &[1, 0, 1], 0, 0);
+        let before = b.clone();
+
+        let capture = b.preview(South, mv(1)).unwrap();
+        assert_eq!(
+            (capture.outcome.end_side, capture.outcome.end_hole),
+            (South, 2)
+        );
+        assert_eq!(capture.outcome.captured, 3);
+        assert!(!capture.outcome.extra_turn);
+        assert_eq!(capture.outcome.next_to_move, North);
+        assert_eq!(
+            contents(&capture.board),
+            (vec![0, 0, 5], vec![0, 0, 1], 0, 3)
+        );
+
+        let extra_turn = b.preview(South, mv(3)).unwrap();
+        assert_eq!(extra_turn.outcome.end_hole, 0);
+        assert!(extra_turn.outcome.extra_turn);
+        assert_eq!(extra_turn.outcome.next_to_move, South);
+
+        assert_eq!(b.preview(South, mv(2)), Err(BoardError::EmptyHole(2)));
+        assert_eq!(b, before);
+
+        let mut played = b.clone();
+        played.apply_move(South, mv(1)).unwrap();
+        assert_eq!(played, capture.board);
+    }
+
+    #[test]
+    fn bad_holes_and_counts_are_errors() {
+        let mut b = Board::new(3, 2);
+        let before = b.clone();
+        assert_eq!(b.try_beans(North, 0), Ok(0));
+        assert_eq!(b.try_beans(North, 3), Ok(2));
+        assert_eq!(b.try_beans(North, 4), Err(BoardError::NoSuchHole(4)));
+        assert_eq!(b.try_beans(South, -1), Err(BoardError::NoSuchHole(-1)));
+        assert_eq!(b.set_beans(South, 4, 1), Err(BoardError::NoSuchHole(4)));
+        assert_eq!(
+            b.set_beans(South, 1, -1),
+            Err(BoardError::NegativeBeans(-1))
+        );
+        assert_eq!(
+            b.move_to_pot(South, 0, South),
+            Err(BoardError::NoSuchHole(0))
+        );
+        assert_eq!(b, before);
+    }
+
+    #[test]
+    fn checked_construction() {
+        let b = Board::from_contents(&[1, 2], &[3, 4], 5, 6, Rules::oware()).unwrap();
+        assert_eq!(contents(&b), (vec![1, 2], vec![3, 4], 5, 6));
+        assert_eq!(b.rules(), Rules::oware());
+        assert_eq!(b.hash(), b.compute_hash());
+
+        let err = |north: &[i32], south: &[i32], north_pot: i32| {
+            Board::from_contents(north, south, north_pot, 0, Rules::kalah()).unwrap_err()
+        };
+        assert_eq!(err(&[], &[], 0), BoardError::BadHoleCount(0));
+        assert_eq!(
+            err(&[1, 2], &[3], 0),
+            BoardError::SidesDiffer { north: 2, south: 1 }
+        );
+        assert_eq!(err(&[1, -2], &[3, 4], 0), BoardError::NegativeBeans(-2));
+        assert_eq!(err(&[1, 2], &[3, 4], -5), BoardError::NegativeBeans(-5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn boards_need_a_hole() {
+        Board::new(0, 4);
+    }
+
+    #[test]
+    fn validate_checks_the_total() {
+        let mut b = Board::new(6, 4);
+        assert_eq!(b.validate(48), Ok(()));
+        assert_eq!(
+            b.validate(50),
+            Err(BoardError::WrongTotal {
+                expected: 50,
+                found: 48
+            })
+        );
+
+        b.apply_move(South, mv(3)).unwrap();
+        b.end_game(North);
+        assert_eq!(b.validate(48), Ok(()));
+
+        // bypass set_beans to get a pit no board should have
+        b.pits.set(North, 2, -1);
+        assert_eq!(b.validate(47), Err(BoardError::NegativeBeans(-1)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "broken board")]
+    fn broken_boards_are_caught_in_debug_builds() {
+        let mut b = Board::new(3, 2);
+        // a pit broken behind the board's back (hash and all) shows up as soon as a move is made
+        b.pits.set(North, 3, -1);
+        b.hash = b.compute_hash();
+        b.mirror_hash = b.compute_mirror_hash();
+        b.apply_move(South, mv(1)).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn beans_of_a_bad_hole_panics() {
+        Board::new(3, 2).beans(South, 4);
+    }
+
+    #[test]
+    fn illegal_moves_leave_the_board_alone() {
+        let mut b = board_from(&[1, 1, 1], &[1, 0, 1], 0, 0);
+        let before = contents(&b);
+        assert_eq!(Move::new(0), Err(BoardError::NoSuchHole(0)));
+        assert_eq!(Move::new(-1), Err(BoardError::NoSuchHole(-1)));
+        assert_eq!(b.check_move(South, 0), Err(BoardError::NoSuchHole(0)));
+        assert_eq!(b.apply_move(South, mv(4)), Err(BoardError::NoSuchHole(4)));
+        assert_eq!(b.apply_move(South, mv(2)), Err(BoardError::EmptyHole(2)));
+        assert_eq!(contents(&b), before);
+    }
+
+    #[test]
+    fn last_bean_in_own_pot_gives_another_turn() {
+        let mut b = Board::new(4, 4);
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (South, 0));
+        assert!(outcome.extra_turn);
+        assert_eq!(outcome.next_to_move, South);
+        assert_eq!(outcome.captured, 0);
+        assert!(!outcome.game_over());
+
+        // north sows towards hole 1 and then its own pot
+        let outcome = b.apply_move(North, mv(4)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 0));
+        assert!(outcome.extra_turn);
+        assert_eq!(outcome.next_to_move, North);
+        assert_eq!(b.beans(North, 0), 1);
+    }
+
+    #[test]
+    fn other_moves_pass_the_turn() {
+        let mut b = Board::new(4, 4);
+        let outcome = b.apply_move(South, mv(2)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 4));
+        assert!(!outcome.extra_turn);
+        assert_eq!(outcome.next_to_move, North);
+    }
+
+    #[test]
+    fn opponents_pot_is_skipped() {
+        let mut b = board_from(&[0, 0], &[1, 4], 0, 0);
+        let outcome = b.apply_move(South, mv(2)).unwrap();
+        // S pot, N2, N1, (N pot skipped) S1
+        assert_eq!((outcome.end_side, outcome.end_hole), (South, 1));
+        assert_eq!(contents(&b), (vec![1, 1], vec![2, 0], 0, 1));
+    }
+
+    #[test]
+    fn capture_from_own_empty_hole() {
+        let mut b = board_from(&[2, 5, 3], &[1, 0, 1], 0, 0);
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (South, 2));
+        assert_eq!(outcome.captured, 6);
+        assert_eq!(contents(&b), (vec![2, 0, 3], vec![0, 0, 1], 0, 6));
+        assert_eq!(outcome.next_to_move, North);
+    }
+
+    #[test]
+    fn north_captures_too() {
+        let mut b = board_from(&[0, 1, 1], &[4, 2, 2], 0, 0);
+        let outcome = b.apply_move(North, mv(2)).unwrap();
+        assert_eq!(outcome.captured, 5);
+        assert_eq!(contents(&b), (vec![0, 0, 1], vec![0, 2, 2], 5, 0));
+    }
+
+    #[test]
+    fn no_capture_when_opposite_hole_is_empty() {
+        let mut b = board_from(&[2, 0, 3], &[1, 0, 1], 0, 0);
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!(outcome.captured, 0);
+        assert_eq!(contents(&b), (vec![2, 0, 3], vec![0, 1, 1], 0, 0));
+    }
+
+    #[test]
+    fn no_capture_when_landing_hole_was_not_empty() {
+        let mut b = board_from(&[2, 5, 3], &[1, 2, 1], 0, 0);
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!(outcome.captured, 0);
+        assert_eq!(b.beans(South, 2), 3);
+    }
+
+    #[test]
+    fn no_capture_in_opponents_empty_hole() {
+        let mut b = board_from(&[1, 0, 3], &[0, 0, 3], 0, 0);
+        let outcome = b.apply_move(South, mv(3)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 2));
+        assert_eq!(outcome.captured, 0);
+        assert_eq!(contents(&b), (vec![1, 1, 4], vec![0, 0, 0], 0, 1));
+    }
+
+    #[test]
+    fn capture_after_a_full_lap_into_the_emptied_hole() {
+        // 5 beans on a 2-hole board go round once: S2, S pot, N2, N1 and back to S1.
+        let mut b = board_from(&[1, 1], &[5, 0], 0, 0);
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (South, 1));
+        assert_eq!(outcome.captured, 3);
+        assert_eq!(contents(&b), (vec![0, 2], vec![0, 1], 0, 4));
+    }
+
+    #[test]
+    fn capture_that_empties_the_opponent_ends_the_game() {
+        let mut b = board_from(&[0, 4], &[1, 0], 0, 0);
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!(outcome.captured, 5);
+        assert_eq!(
+            outcome.sweep,
+            Some(Sweep {
+                empty_side: North,
+                from_side: South,
+                pot_owner: South,
+                beans: 0,
+            })
+        );
+        assert_eq!(contents(&b), (vec![0, 0], vec![0, 0], 0, 5));
+    }
+
+    #[test]
+    fn game_ends_when_the_side_to_move_next_has_nothing() {
+        let mut b = board_from(&[0, 0, 0], &[2, 0, 3], 7, 0);
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert!(outcome.game_over());
+        assert_eq!(outcome.next_to_move, North);
+        assert_eq!(
+            outcome.sweep,
+            Some(Sweep {
+                empty_side: North,
+                from_side: South,
+                pot_owner: South,
+                beans: 5,
+            })
+        );
+        assert_eq!(contents(&b), (vec![0, 0, 0], vec![0, 0, 0], 7, 5));
+    }
+
+    #[test]
+    fn extra_turn_with_nothing_left_to_sow_ends_the_game() {
+        let mut b = board_from(&[3, 1], &[0, 1], 0, 0);
+        let outcome = b.apply_move(South, mv(2)).unwrap();
+        assert!(outcome.extra_turn);
+        assert_eq!(
+            outcome.sweep,
+            Some(Sweep {
+                empty_side: South,
+                from_side: North,
+                pot_owner: North,
+                beans: 4,
+            })
+        );
+        assert_eq!(contents(&b), (vec![0, 0], vec![0, 0], 4, 1));
+    }
+
+    #[test]
+    fn emptying_own_side_does_not_end_the_game_if_the_opponent_can_move() {
+        let mut b = board_from(&[1, 1], &[0, 3], 0, 0);
+        let outcome = b.apply_move(South, mv(2)).unwrap();
+        assert_eq!(b.beans_in_play(South), 0);
+        assert_eq!(outcome.next_to_move, North);
+        assert!(!outcome.game_over());
+        assert!(!b.is_game_over(North));
+    }
+
+    #[test]
+    fn sweep_of_a_finished_position() {
+        let mut b = board_from(&[0, 0], &[2, 3], 1, 1);
+        assert!(b.is_game_over(North));
+        assert!(!b.is_game_over(South));
+        let sweep = b.sweep(North);
+        assert_eq!(sweep.beans, 5);
+        assert_eq!(contents(&b), (vec![0, 0], vec![0, 0], 1, 6));
+    }
+
+    #[test]
+    fn capture_from_empty_opposite_hole_under_empty_capture_rule() {
+        let mut b = board_from(&[2, 0, 3], &[1, 0, 1], 0, 0);
+        b.set_rules(Rules::kalah().with_capture(CaptureRule::EvenIfOppositeEmpty));
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!(outcome.captured, 1);
+        assert_eq!(contents(&b), (vec![2, 0, 3], vec![0, 0, 1], 0, 1));
+    }
+
+    #[test]
+    fn nothing_is_captured_under_no_capture_rule() {
+        let mut b = board_from(&[2, 5, 3], &[1, 0, 1], 0, 0);
+        b.set_rules(Rules::kalah().with_capture(CaptureRule::NoCapture));
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!(outcome.captured, 0);
+        assert_eq!(contents(&b), (vec![2, 5, 3], vec![0, 1, 1], 0, 0));
+    }
+
+    #[test]
+    fn sweep_to_the_side_that_ran_out() {
+        let mut b = board_from(&[0, 0, 0], &[2, 0, 3], 7, 0);
+        b.set_rules(Rules::kalah().with_sweep(SweepRule::ToEmptySide));
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!(
+            outcome.sweep,
+            Some(Sweep {
+                empty_side: North,
+                from_side: South,
+                pot_owner: North,
+                beans: 5,
+            })
+        );
+        assert_eq!(contents(&b), (vec![0, 0, 0], vec![0, 0, 0], 12, 0));
+    }
+
+    #[test]
+    fn rules_are_part_of_the_position_key() {
+        let b1 = Board::new(6, 4);
+        let b2 = Board::new_with_rules(6, 4, Rules::kalah().with_capture(CaptureRule::NoCapture));
+        assert_eq!(b1.hash(), b2.hash());
+        assert_ne!(b1.position_key(South), b2.position_key(South));
+    }
+
+    #[test]
+    fn the_number_of_holes_is_part_of_the_position_key() {
+        // North's beans are in different places, though the hashes of the pits agree.
+        let narrow = board_from(&[0, 0, 2], &[1, 0, 0], 0, 0);
+        let wide = board_from(&[0, 0, 2, 0], &[1, 0, 0, 0], 0, 0);
+        assert_eq!(narrow.hash(), wide.hash());
+        for s in [South, North] {
+            assert_ne!(narrow.position_key(s), wide.position_key(s));
+            assert_ne!(narrow.canonical_key(s), wide.canonical_key(s));
+        }
+    }
+
+    fn oware_board_from(north: &[i32], south: &[i32]) -> Board {
+        let mut b = board_from(north, south, 0, 0);
+        b.set_rules(Rules::oware());
+        b
+    }
+
+    #[test]
+    fn oware_sowing_skips_the_pots() {
+        let mut b = oware_board_from(&[4, 4, 4, 4, 4, 4], &[4, 4, 4, 4, 4, 4]);
+        let outcome = b.apply_move(South, mv(5)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 4));
+        assert!(!outcome.extra_turn);
+        assert_eq!(
+            contents(&b),
+            (vec![4, 4, 4, 5, 5, 5], vec![4, 4, 4, 4, 0, 5], 0, 0)
+        );
+
+        let outcome = b.apply_move(North, mv(1)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (South, 4));
+        assert_eq!(
+            contents(&b),
+            (vec![0, 4, 4, 5, 5, 5], vec![5, 5, 5, 5, 0, 5], 0, 0)
+        );
+    }
+
+    #[test]
+    fn oware_laps_skip_the_hole_being_sown() {
+        let mut b = oware_board_from(&[0, 0, 0, 0, 0, 0], &[12, 0, 0, 0, 0, 0]);
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        // 11 beans go round to north's hole 1, then south's hole 1 is skipped
+        assert_eq!((outcome.end_side, outcome.end_hole), (South, 2));
+        assert_eq!(
+            contents(&b),
+            (vec![1, 1, 1, 1, 1, 1], vec![0, 2, 1, 1, 1, 1], 0, 0)
+        );
+    }
+
+    #[test]
+    fn oware_captures_a_run_of_twos_and_threes() {
+        let mut b = oware_board_from(&[1, 1, 1, 1, 1, 2], &[1, 0, 0, 0, 0, 3]);
+        let outcome = b.apply_move(South, mv(6)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 4));
+        assert_eq!(outcome.captured, 7);
+        assert_eq!(
+            contents(&b),
+            (vec![1, 1, 1, 0, 0, 0], vec![1, 0, 0, 0, 0, 0], 0, 7)
+        );
+    }
+
+    #[test]
+    fn oware_capture_run_stops_at_other_counts() {
+        let mut b = oware_board_from(&[1, 1, 1, 1, 3, 1], &[1, 0, 0, 0, 0, 3]);
+        let outcome = b.apply_move(South, mv(6)).unwrap();
+        assert_eq!(outcome.captured, 2);
+        assert_eq!(
+            contents(&b),
+            (vec![1, 1, 1, 0, 4, 2], vec![1, 0, 0, 0, 0, 0], 0, 2)
+        );
+    }
+
+    #[test]
+    fn oware_north_captures_on_south_side() {
+        let mut b = oware_board_from(&[3, 0, 0, 0, 0, 1], &[1, 2, 1, 5, 0, 0]);
+        let outcome = b.apply_move(North, mv(1)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (South, 3));
+        assert_eq!(outcome.captured, 7);
+        assert_eq!(
+            contents(&b),
+            (vec![0, 0, 0, 0, 0, 1], vec![0, 0, 0, 5, 0, 0], 7, 0)
+        );
+    }
+
+    #[test]
+    fn oware_no_capture_on_own_side() {
+        let mut b = oware_board_from(&[1, 1, 1, 1, 1, 1], &[2, 1, 0, 0, 0, 0]);
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!(outcome.captured, 0);
+        assert_eq!(b.beans(South, 2), 2);
+    }
+
+    #[test]
+    fn oware_grand_slam_captures_nothing() {
+        let mut b = oware_board_from(&[0, 0, 0, 1, 1, 1], &[1, 0, 0, 0, 0, 3]);
+        let outcome = b.apply_move(South, mv(6)).unwrap();
+        assert_eq!(outcome.captured, 0);
+        assert_eq!(
+            contents(&b),
+            (vec![0, 0, 0, 2, 2, 2], vec![1, 0, 0, 0, 0, 0], 0, 0)
+        );
+    }
+
+    #[test]
+    fn oware_empty_opponent_must_be_fed() {
+        let mut b = oware_board_from(&[0, 0, 0, 0, 0, 0], &[1, 0, 0, 0, 0, 1]);
+        assert!(!b.is_legal_move(South, 1));
+        assert!(b.is_legal_move(South, 6));
+        assert!(!b.is_game_over(South));
+        assert_eq!(b.apply_move(South, mv(1)), Err(BoardError::MustFeed(1)));
+        assert!(b.apply_move(South, mv(6)).is_ok());
+        assert_eq!(b.beans(North, 6), 1);
+    }
+
+    #[test]
+    fn oware_game_ends_when_the_opponent_cannot_be_fed() {
+        let mut b = oware_board_from(&[1, 0, 0, 0, 0, 0], &[1, 0, 0, 0, 0, 0]);
+        // north's last bean makes south's hole 1 hold 2, but capturing it would be a grand slam.
+        // north is then empty and south's 2 beans cannot reach it.
+        let outcome = b.apply_move(North, mv(1)).unwrap();
+        assert_eq!(outcome.captured, 0);
+        assert_eq!(
+            outcome.sweep,
+            Some(Sweep {
+                empty_side: North,
+                from_side: South,
+                pot_owner: South,
+                beans: 2,
+            })
+        );
+        assert_eq!(
+            contents(&b),
+            (vec![0, 0, 0, 0, 0, 0], vec![0, 0, 0, 0, 0, 0], 0, 2)
+        );
+    }
+
+    #[test]
+    fn kalah_is_still_kalah() {
+        // 13 beans from south's hole 1 pass through south's pot and come back to hole 1.
+        let mut b = board_from(&[0, 0, 0, 0, 0, 0], &[13, 0, 0, 0, 0, 0], 0, 0);
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (South, 1));
+        assert_eq!(outcome.captured, 2);
+        assert_eq!(b.beans(South, 0), 3);
+    }
+
+    #[test]
+    fn random_openings_are_reproducible() {
+        let mut b1 = Board::new(6, 4);
+        let mut b2 = Board::new(6, 4);
+        let s1 = b1.play_random_opening(South, 6, &mut Rng::new(7));
+        let s2 = b2.play_random_opening(South, 6, &mut Rng::new(7));
+        assert_eq!(s1, s2);
+        assert_eq!(contents(&b1), contents(&b2));
+        assert_ne!(contents(&b1), contents(&Board::new(6, 4)));
+        assert_eq!(b1.total_beans(), 48);
+    }
+
+    #[test]
+    fn unmake_restores_the_board_exactly() {
+        let mut rng = Rng::new(11);
+        let rules = [
+            Rules::kalah(),
+            Rules::kalah().with_capture(CaptureRule::EvenIfOppositeEmpty),
+            Rules::kalah().with_sweep(SweepRule::ToEmptySide),
+            Rules::oware(),
+            Rules::kalah()
+                .with_sowing(SowingRule::PlayersChoice)
+                .with_stores(StoreRule::Both),
+            Rules::oware().with_sowing(SowingRule::Always(Direction::Clockwise)),
+        ];
+        for game in 0..300 {
+            let rules = rules[game % rules.len()];
+            let mut b = Board::new_with_rules(rng.range(1, 6), rng.range(1, 8), rules);
+            let mut side = South;
+            let mut history = Vec::new();
+            while !b.is_game_over(side) && history.len() < 100 {
+                let legal: Vec<Move> = b.legal_moves(side).collect();
+                let hole = legal[rng.range(0, legal.len() as i32 - 1) as usize];
+
+                let before = b.clone();
+                let undo = b.make_move(side, hole).unwrap();
+                let mut expected = before.clone();
+                assert_eq!(expected.apply_move(side, hole), Ok(undo.outcome));
+                assert_eq!(b, expected);
+
+                b.unmake_move(&undo);
+                assert_eq!(b, before);
+                b.make_move(side, hole).unwrap();
+
+                side = undo.outcome.next_to_move;
+                history.push((before, undo));
+            }
+
+            // and all the way back to the start
+            while let Some((before, undo)) = history.pop() {
+                b.unmake_move(&undo);
+                assert_eq!(b, before);
+            }
+        }
+    }
+
+    #[test]
+    fn unmake_a_capture_and_sweep() {
+        let mut b = board_from(&[0, 2], &[1, 0], 3, 4);
+        let before = b.clone();
+        let undo = b.make_move(South, mv(1)).unwrap();
+        assert_eq!(undo.outcome.captured, 3);
+        assert!(undo.outcome.game_over());
+        assert_eq!(contents(&b), (vec![0, 0], vec![0, 0], 3, 7));
+
+        b.unmake_move(&undo);
+        assert_eq!(b, before);
+        assert_eq!(b.hash(), b.compute_hash());
+    }
+
+    #[test]
+    fn compact_boards_play_the_same() {
+        let mut rng = Rng::new(15);
+        for game in 0..100 {
+            let rules = if game % 2 == 0 {
+                Rules::kalah()
+            } else {
+                Rules::oware()
+            };
+            let mut b = Board::new_with_rules(rng.range(1, 8), rng.range(1, 6), rules);
+            let mut compact = CompactBoard::try_from(&b).unwrap();
+            let mut side = South;
+            // a CompactBoard's undo records are Copy, and take it back to the start.
+            fn is_copy<T: Copy>(_: &T) {}
+            let start = compact;
+            let mut undos = Vec::new();
+            // Oware games can go round in circles, so stop after a while.
+            for _ in 0..200 {
+                if b.is_game_over(side) {
+                    assert!(compact.is_game_over(side));
+                    break;
+                }
+                assert!(!compact.is_game_over(side));
+                let legal: Vec<Move> = b.legal_moves(side).collect();
+                assert_eq!(compact.legal_moves(side).collect::<Vec<Move>>(), legal);
+
+                let hole = legal[rng.range(0, legal.len() as i32 - 1) as usize];
+                let before = compact;
+                let undo = compact.make_move(side, hole).unwrap();
+                is_copy(&undo);
+                let outcome = b.apply_move(side, hole).unwrap();
+                assert_eq!(undo.outcome, outcome);
+                assert_eq!(Board::from(compact), b);
+                assert_eq!(compact.hash(), b.hash());
+
+                compact.unmake_move(&undo);
+                assert_eq!(compact, before);
+                compact.make_move(side, hole).unwrap();
+                undos.push(undo);
+                side = outcome.next_to_move;
+            }
+            for undo in undos.iter().rev() {
+                compact.unmake_move(undo);
+            }
+            assert_eq!(compact, start);
+        }
+    }
+
+    // counts the allocations made on each thread, so that a test can check that some code
+    // makes none.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn compact_boards_do_not_allocate() {
+        let all_rules = [
+            Rules::kalah(),
+            Rules::oware(),
+            Rules::kalah().with_stores(StoreRule::Both),
+            Rules::kalah().with_sowing(SowingRule::Always(Direction::Clockwise)),
+            Rules::oware().with_sowing(SowingRule::PlayersChoice),
+        ];
+        let mut rng = Rng::new(18);
+        for rules in all_rules {
+            for _ in 0..20 {
+                let b = Board::new_with_rules(rng.range(1, 8), rng.range(1, 20), rules);
+                let mut compact = CompactBoard::try_from(&b).unwrap();
+                let mut undos = Vec::with_capacity(200);
+                let mut side = South;
+
+                let before = ALLOCATIONS.with(Cell::get);
+                while undos.len() < 200 && !compact.is_game_over(side) {
+                    let legal = compact.legal_moves(side).count() as i32;
+                    let pick = rng.range(0, legal - 1) as usize;
+                    let mv = compact.legal_moves(side).nth(pick).unwrap();
+                    let undo = compact.make_move(side, mv).unwrap();
+                    side = undo.outcome.next_to_move;
+                    undos.push(undo);
+                }
+                for undo in undos.iter().rev() {
+                    compact.unmake_move(undo);
+                }
+                assert_eq!(ALLOCATIONS.with(Cell::get), before);
+                assert_eq!(Board::from(compact), b);
+            }
+        }
+    }
+
+    #[test]
+    fn boards_too_large_for_the_compact_form() {
+        let b = Board::new(crate::pits::MAX_INLINE_HOLES + 1, 1);
+        assert_eq!(CompactBoard::try_from(&b), Err(BoardError::TooLarge));
+
+        let mut b = Board::new(6, 0);
+        b.set_beans(North, 1, 65535).unwrap();
+        let mut compact = CompactBoard::try_from(&b).unwrap();
+        assert_eq!(compact.set_beans(South, 1, 1), Err(BoardError::TooLarge));
+        b.set_beans(South, 1, 1).unwrap();
+        assert_eq!(CompactBoard::try_from(&b), Err(BoardError::TooLarge));
+    }
+
+    #[test]
+    fn compact_boards_are_copy_and_hashable() {
+        let mut seen = std::collections::HashSet::new();
+        let start = CompactBoard::try_from(&Board::new(6, 4)).unwrap();
+        for hole in 1..=6 {
+            let mut b = start;
+            b.apply_move(South, mv(hole)).unwrap();
+            assert!(seen.insert(b));
+        }
+        assert!(!seen.contains(&start));
+        assert_eq!(Board::from(start), Board::new(6, 4));
+    }
+
+    #[test]
+    fn mirroring_swaps_the_sides() {
+        let b = board_from(&[1, 2, 3], &[4, 5, 6], 7, 8);
+        let m = b.mirrored();
+        assert_eq!(contents(&m), (vec![6, 5, 4], vec![3, 2, 1], 8, 7));
+        assert_eq!(m.mirror_pit(South, 1), (North, 3));
+        assert_eq!(m.mirror_pit(North, 0), (South, 0));
+        assert_eq!(m.mirrored(), b);
+        assert_eq!(m.hash(), m.compute_hash());
+    }
+
+    #[test]
+    fn mirrored_boards_play_the_same() {
+        let mut rng = Rng::new(17);
+        for game in 0..100 {
+            let rules = match game % 3 {
+                0 => Rules::kalah().with_sweep(SweepRule::ToEmptySide),
+                1 => Rules::oware(),
+                _ => Rules::kalah().with_sowing(SowingRule::PlayersChoice),
+            };
+            let mut b = Board::new_with_rules(rng.range(1, 6), rng.range(1, 5), rules);
+            let mut m = b.mirrored();
+            let mut side = South;
+            // Oware games can go round in circles, so stop after a while.
+            for _ in 0..200 {
+                assert_eq!(b.canonical_key(side), m.canonical_key(side.opponent()));
+                if b.is_game_over(side) {
+                    assert!(m.is_game_over(side.opponent()));
+                    break;
+                }
+                let legal: Vec<Move> = b.legal_moves(side).collect();
+                let hole = legal[rng.range(0, legal.len() as i32 - 1) as usize];
+                let outcome = b.apply_move(side, hole).unwrap();
+                let mirror_outcome = m
+                    .apply_move(side.opponent(), hole.mirrored(b.holes()))
+                    .unwrap();
+                assert_eq!(mirror_outcome.next_to_move, outcome.next_to_move.opponent());
+                assert_eq!(mirror_outcome.captured, outcome.captured);
+                assert_eq!(m, b.mirrored());
+                assert_eq!(m.hash(), m.compute_hash());
+                side = outcome.next_to_move;
+            }
+        }
+    }
+
+    #[test]
+    fn the_sowing_path_goes_both_ways() {
+        let ring = |direction: Direction| {
+            let mut pits = vec![(South, 1)];
+            while pits.len() < 6 {
+                let (side, hole) = *pits.last().unwrap();
+                pits.push(Board::get_next_location(side, hole, direction, 2));
+            }
+            pits
+        };
+        assert_eq!(
+            ring(Direction::CounterClockwise),
+            vec![
+                (South, 1),
+                (South, 2),
+                (South, 0),
+                (North, 2),
+                (North, 1),
+                (North, 0)
+            ]
+        );
+        assert_eq!(
+            ring(Direction::Clockwise),
+            vec![
+                (South, 1),
+                (North, 0),
+                (North, 1),
+                (North, 2),
+                (South, 0),
+                (South, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn clockwise_sowing() {
+        let clockwise = Rules::kalah().with_sowing(SowingRule::Always(Direction::Clockwise));
+        // South's hole 2 goes to its hole 1 and then past North's pot to North's holes 1 and 2.
+        let mut b = board_from(&[0, 0, 0], &[0, 3, 0], 0, 0);
+        b.set_rules(clockwise);
+        let outcome = b.apply_move(South, mv(2)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 2));
+        assert_eq!(contents(&b), (vec![1, 1, 0], vec![1, 0, 0], 0, 0));
+
+        // North's hole 1 comes round to North's pot last, which earns another turn.
+        let mut b = board_from(&[0, 0, 0], &[0, 0, 0], 0, 0);
+        b.set_beans(North, 1, 6).unwrap();
+        b.set_beans(South, 2, 1).unwrap();
+        b.set_rules(clockwise);
+        let outcome = b.apply_move(North, mv(1)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 0));
+        assert!(outcome.extra_turn);
+        assert_eq!(contents(&b), (vec![0, 1, 1], vec![1, 2, 1], 1, 0));
+    }
+
+    #[test]
+    fn sowing_into_both_stores() {
+        let mut b = board_from(&[0, 0], &[0, 4], 0, 0);
+        b.set_rules(Rules::kalah().with_stores(StoreRule::Both));
+        // South's pot, North's holes, then North's pot, where the last bean earns nothing.
+        let outcome = b.apply_move(South, mv(2)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 0));
+        assert!(!outcome.extra_turn);
+        assert_eq!(outcome.next_to_move, North);
+        assert_eq!(contents(&b), (vec![1, 1], vec![0, 0], 1, 1));
+    }
+
+    #[test]
+    fn players_choose_the_direction() {
+        let mut b = board_from(&[1, 1], &[0, 2], 0, 0);
+        b.set_rules(Rules::kalah().with_sowing(SowingRule::PlayersChoice));
+        let moves: Vec<String> = b.legal_moves(South).map(|m| m.to_string()).collect();
+        assert_eq!(moves, vec!["2ccw", "2cw"]);
+        assert_eq!(b.check_move(South, 2), Err(BoardError::NoDirection(2)));
+
+        let clockwise = mv(2).towards(Direction::Clockwise);
+        // clockwise, South's hole 1 leads past North's pot to North's hole 1.
+        let outcome = b.preview(South, clockwise).unwrap().outcome;
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 1));
+        let outcome = b
+            .apply_move(South, mv(2).towards(Direction::CounterClockwise))
+            .unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 2));
+
+        // under a fixed direction a move may name it, but not the other one.
+        let b = board_from(&[1, 1], &[0, 2], 0, 0);
+        let counter_clockwise = mv(2).towards(Direction::CounterClockwise);
+        assert_eq!(
+            b.check_sowing(South, counter_clockwise),
+            Ok(counter_clockwise)
+        );
+        assert_eq!(
+            b.check_sowing(South, clockwise),
+            Err(BoardError::WrongDirection(2))
+        );
+    }
+
+    #[test]
+    fn oware_captures_run_back_along_a_clockwise_sowing() {
+        // sowing clockwise, South's hole 1 reaches North's holes 1, 2 and 3, and the run of 2s
+        // and 3s is taken back from hole 3 to hole 1.
+        let mut b = oware_board_from(&[1, 2, 1, 0, 0, 1], &[3, 0, 0, 0, 0, 1]);
+        b.set_rules(Rules::oware().with_sowing(SowingRule::Always(Direction::Clockwise)));
+        let outcome = b.apply_move(South, mv(1)).unwrap();
+        assert_eq!((outcome.end_side, outcome.end_hole), (North, 3));
+        assert_eq!(outcome.captured, 7);
+        assert_eq!(
+            contents(&b),
+            (vec![0, 0, 0, 0, 0, 1], vec![0, 0, 0, 0, 0, 1], 0, 7)
+        );
+    }
+
+    #[test]
+    fn moves_round_trip_through_text() {
+        for text in ["3", "3cw", "12ccw"] {
+            assert_eq!(text.parse::<Move>().unwrap().to_string(), text);
+        }
+        assert_eq!(
+            "4cw".parse::<Move>(),
+            Ok(mv(4).towards(Direction::Clockwise))
+        );
+        assert!("0".parse::<Move>().is_err());
+        assert!("cw".parse::<Move>().is_err());
+        assert!("3up".parse::<Move>().is_err());
     }
 }
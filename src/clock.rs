@@ -0,0 +1,235 @@
+#![allow(dead_code)]
+
+// Chess clocks for timed games. Each side has a clock that runs while it is that side's turn,
+// from the moment the turn passes to it until its complete move is made. A side whose clock runs
+// down to nothing loses on time (see Game::set_clock). The time is read from a TimeSource, the
+// system's steady clock in real games and a ManualTime that only moves when told to in tests.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::board::Side;
+
+// where a Clock gets the time from: the time since some fixed moment, which never goes back.
+pub trait TimeSource {
+    fn now(&self) -> Duration;
+}
+
+// the real time, measured from when it was made.
+pub struct SystemTime {
+    start: Instant,
+}
+
+impl SystemTime {
+    pub fn new() -> Self {
+        SystemTime {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemTime {
+    fn default() -> Self {
+        SystemTime::new()
+    }
+}
+
+impl TimeSource for SystemTime {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// a time that stands still until advance is called. Clones share the same time, so a test can
+// keep one to move the time on while the clock it was given to reads it.
+#[derive(Clone, Debug, Default)]
+pub struct ManualTime {
+    now: Rc<Cell<Duration>>,
+}
+
+impl ManualTime {
+    pub fn new() -> Self {
+        ManualTime::default()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+// how much time each side gets.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TimeControl {
+    // each side starts with base for the whole game, and increment is added to its clock after
+    // every complete move it makes (an increment of 0 makes it plain sudden death).
+    Increment { base: Duration, increment: Duration },
+    // each complete move must be made within the time; time left over is not carried over.
+    PerMove(Duration),
+}
+
+impl TimeControl {
+    // the time each side has before its first move.
+    fn initial(&self) -> Duration {
+        match *self {
+            TimeControl::Increment { base, .. } => base,
+            TimeControl::PerMove(time) => time,
+        }
+    }
+}
+
+pub struct Clock {
+    control: TimeControl,
+    // the time left on each side's clock, South's first, as it was when the clock last
+    // stopped or started
+    remaining: [Duration; 2],
+    // the side whose clock is running and the time it started
+    running: Option<(Side, Duration)>,
+    source: Box<dyn TimeSource>,
+}
+
+fn index(side: Side) -> usize {
+    match side {
+        Side::South => 0,
+        Side::North => 1,
+    }
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, source: Box<dyn TimeSource>) -> Self {
+        Clock {
+            control,
+            remaining: [control.initial(); 2],
+            running: None,
+            source,
+        }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    // the time side has left, counting down while its clock runs, and never below zero.
+    pub fn remaining(&self, side: Side) -> Duration {
+        let remaining = self.remaining[index(side)];
+        match self.running {
+            Some((running, started)) if running == side => {
+                remaining.saturating_sub(self.source.now() - started)
+            }
+            _ => remaining,
+        }
+    }
+
+    // the side whose clock is running, if any.
+    pub fn running(&self) -> Option<Side> {
+        self.running.map(|(side, _)| side)
+    }
+
+    // the side whose clock is running, if it has run out of time.
+    pub fn flag_fallen(&self) -> Option<Side> {
+        self.running()
+            .filter(|&side| self.remaining(side).is_zero())
+    }
+
+    // starts side's clock, for a new move under PerMove, stopping the other one if it runs.
+    pub fn start(&mut self, side: Side) {
+        self.stop();
+        if let TimeControl::PerMove(time) = self.control {
+            self.remaining[index(side)] = time;
+        }
+        self.running = Some((side, self.source.now()));
+    }
+
+    // stops the running clock, if any, taking the time used off it.
+    pub fn stop(&mut self) {
+        if let Some((side, _)) = self.running {
+            self.remaining[index(side)] = self.remaining(side);
+            self.running = None;
+        }
+    }
+
+    // the running side has made its complete move: its clock stops, with the increment added,
+    // and the other side's starts.
+    pub fn press(&mut self) {
+        if let Some(side) = self.running() {
+            self.stop();
+            if let TimeControl::Increment { increment, .. } = self.control {
+                self.remaining[index(side)] += increment;
+            }
+            self.start(side.opponent());
+        }
+    }
+}
+
+// a time as a clock shows it: minutes, seconds and tenths, such as 4:05.3.
+pub fn clock_text(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn increments_are_added_after_each_move() {
+        let time = ManualTime::new();
+        let control = TimeControl::Increment {
+            base: secs(60),
+            increment: secs(2),
+        };
+        let mut clock = Clock::new(control, Box::new(time.clone()));
+        assert_eq!(clock.running(), None);
+
+        clock.start(Side::South);
+        time.advance(secs(10));
+        assert_eq!(clock.remaining(Side::South), secs(50));
+        assert_eq!(clock.remaining(Side::North), secs(60));
+
+        clock.press();
+        assert_eq!(clock.remaining(Side::South), secs(52));
+        assert_eq!(clock.running(), Some(Side::North));
+        time.advance(secs(5));
+        assert_eq!(clock.remaining(Side::North), secs(55));
+
+        clock.stop();
+        time.advance(secs(100));
+        assert_eq!(clock.remaining(Side::North), secs(55));
+        assert_eq!(clock.flag_fallen(), None);
+    }
+
+    #[test]
+    fn per_move_time_is_not_carried_over() {
+        let time = ManualTime::new();
+        let mut clock = Clock::new(TimeControl::PerMove(secs(5)), Box::new(time.clone()));
+        clock.start(Side::North);
+        time.advance(secs(1));
+        clock.press();
+        time.advance(secs(4));
+        clock.press();
+        assert_eq!(clock.remaining(Side::North), secs(5));
+        assert_eq!(clock.remaining(Side::South), secs(1));
+
+        time.advance(secs(6));
+        assert_eq!(clock.remaining(Side::North), Duration::ZERO);
+        assert_eq!(clock.flag_fallen(), Some(Side::North));
+    }
+
+    #[test]
+    fn clock_text_shows_tenths() {
+        assert_eq!(clock_text(Duration::from_millis(245_370)), "4:05.3");
+        assert_eq!(clock_text(Duration::ZERO), "0:00.0");
+        assert_eq!(clock_text(secs(3600)), "60:00.0");
+    }
+}
@@ -1,9 +1,16 @@
 #![allow(dead_code)]
 
 use std::cmp::Ordering;
+use std::fmt;
+use std::io;
+use std::path::Path;
 
-use crate::board::{Board, Side};
+use crate::board::{Board, BoardError, Move, MoveOutcome, Side, Sweep, Undo};
+use crate::clock::Clock;
+use crate::observer::{GameEvent, GameObserver, GameView, TextRenderer};
 use crate::player::Player;
+use crate::record::{GameRecord, MoveRecord, Sowing, Termination};
+use crate::rng::Rng;
 
 pub struct Game {
     board: Board,
@@ -11,71 +18,472 @@ pub struct Game {
     south: Box<dyn Player>,
     is_over: bool,
     winner: Option<Side>,
+    // how the game ended, once it is over.
+    termination: Option<Termination>,
+    // what to do when a player chooses a move it cannot play, and the Rng for picking a move
+    // in its place.
+    illegal_move_policy: IllegalMovePolicy,
+    rng: Rng,
+    // the clocks in a timed game.
+    clock: Option<Clock>,
+    // when the game is ended before it is played out.
+    adjudication: Adjudication,
+    // the side that has offered a draw its opponent has not yet answered.
+    draw_offer: Option<Side>,
+    // the side that makes the first move.
+    first_to_move: Side,
+    // under the pie rule, the second player may swap sides after the first complete move.
+    pie_rule: bool,
+    sides_swapped: bool,
+    // the record of the game so far: the starting board, every complete move, and the final
+    // sweep once the game is over.
+    start: Board,
+    moves: Vec<MoveRecord>,
+    final_sweep: Option<Sweep>,
+    // the side whose turn it is.
+    side_to_move: Side,
+    // for each move in `moves`, what it takes to unmake its sowings.
+    undos: Vec<Vec<Undo>>,
+    // moves taken back by undo, the last one undone at the end.
+    undone: Vec<Undone>,
+    // the sowings made so far in a complete move that an extra turn has left unfinished, and
+    // what it takes to unmake them.
+    turn_sowings: Vec<Sowing>,
+    turn_undos: Vec<Undo>,
+    // told everything that happens; a TextRenderer unless set_observer says otherwise.
+    observer: Box<dyn GameObserver>,
 }
 
+// what Game::play does when a player chooses a move it cannot play (an empty hole, a hole
+// that is not on the board, a move that starves the opponent...) or no move at all.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum IllegalMovePolicy {
+    // asks the player again, up to this many more times in a complete move, and then the player
+    // forfeits the game.
+    AskAgain(u32),
+    // the player forfeits the game at once.
+    Forfeit,
+    // a legal move is picked at random from an Rng seeded with seed and played instead.
+    RandomMove { seed: u64 },
+}
+
+impl Default for IllegalMovePolicy {
+    fn default() -> Self {
+        IllegalMovePolicy::AskAgain(3)
+    }
+}
+
+// when a game is decided without being played out. By default it never is.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Adjudication {
+    // ends the game as soon as a pot holds more than half of the beans, since beans never leave
+    // a pot and its owner has won whatever happens next.
+    pub majority: bool,
+    // ends the game after this many complete moves, won by whichever side has more in its pot.
+    pub move_limit: Option<usize>,
+}
+
+// where a game stands.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct GameStatus {
-    over: bool,
-    winner: Option<Side>,
+    pub over: bool,
+    // None until the game is over, and for a tie
+    pub winner: Option<Side>,
+    // how the game ended; None until it is over
+    pub termination: Option<Termination>,
+    // the beans in each side's pot
+    pub south_score: i32,
+    pub north_score: i32,
+    // the side to sow next (once the game is over, the side that would have)
+    pub side_to_move: Side,
+}
+
+// what one move submitted with Game::submit_move did.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TurnOutcome {
+    pub side: Side,
+    pub mv: Move,
+    // what the sowing did on the board: captures, extra turn, and the sweep if it ended the game
+    pub outcome: MoveOutcome,
+    // true if side's complete move is finished, false if it has earned another sowing
+    pub turn_complete: bool,
+    pub status: GameStatus,
+}
+
+// why Game::submit_move or Game::swap_sides could not be done.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GameError {
+    // the game is over
+    Over,
+    // the side to move cannot play that move
+    IllegalMove(BoardError),
+    // the side to move ran out of time before making the move, and so has lost
+    OutOfTime,
+    // under the pie rule sides can only be swapped by the player replying to the first move,
+    // before it replies
+    SwapNotAllowed,
+    // a draw can only be accepted when the opponent has offered one
+    NoDrawOffer,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::Over => write!(f, "the game is over"),
+            GameError::IllegalMove(error) => write!(f, "{}", error),
+            GameError::OutOfTime => write!(f, "the time has run out"),
+            GameError::SwapNotAllowed => write!(f, "the sides cannot be swapped now"),
+            GameError::NoDrawOffer => write!(f, "no draw has been offered"),
+        }
+    }
+}
+
+// the player in a seat whose moves are not chosen by a Player but handed to the game through
+// submit_move, for a game driven from outside (see Game::new_driven).
+struct Seat {
+    name: String,
+}
+
+impl Player for Seat {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+
+    fn choose_move(&self, _b: &Board, _s: Side) -> Option<Move> {
+        panic!(
+            "{}'s moves are submitted to the game, so it cannot be played with Game::play",
+            self.name
+        );
+    }
+}
+
+// a move taken back by undo, with what redo needs to put it back as it was.
+struct Undone {
+    record: MoveRecord,
+    // whether the sides were swapped after it
+    swapped: bool,
+    // the side to move after it: the other side, unless the game ended part way through it
+    side_to_move: Side,
+    // who won and how, if the game ended with it
+    ending: Option<(Option<Side>, Termination)>,
 }
 
 impl Game {
     pub fn new(board: Board, south: Box<dyn Player>, north: Box<dyn Player>) -> Self {
-        Game {
+        Game::new_from_position(board, Side::South, south, north)
+    }
+
+    // a game with no Players, for something outside (a GUI, a server, a test) to drive one move
+    // at a time with submit_move, naming the two sides' players.
+    pub fn new_driven(board: Board, side_to_move: Side, south: &str, north: &str) -> Self {
+        let seat = |name: &str| -> Box<dyn Player> {
+            Box::new(Seat {
+                name: name.to_owned(),
+            })
+        };
+        Game::new_from_position(board, side_to_move, seat(south), seat(north))
+    }
+
+    // starts a game from any position (built with Board::set_beans, say, or a random opening)
+    // with either side to move. A position in which side_to_move has no move ends the game at
+    // once.
+    pub fn new_from_position(
+        board: Board,
+        side_to_move: Side,
+        south: Box<dyn Player>,
+        north: Box<dyn Player>,
+    ) -> Self {
+        let mut game = Game {
+            start: board.clone(),
+            moves: Vec::new(),
+            final_sweep: None,
+            side_to_move,
+            undos: Vec::new(),
+            undone: Vec::new(),
+            turn_sowings: Vec::new(),
+            turn_undos: Vec::new(),
             board,
             north,
             south,
             is_over: false,
             winner: None,
+            termination: None,
+            illegal_move_policy: IllegalMovePolicy::default(),
+            rng: Rng::new(0),
+            clock: None,
+            adjudication: Adjudication::default(),
+            draw_offer: None,
+            first_to_move: side_to_move,
+            pie_rule: false,
+            sides_swapped: false,
+            observer: Box::new(TextRenderer),
+        };
+        if game.board.is_game_over(side_to_move) {
+            game.final_sweep = Some(game.board.end_game(side_to_move));
+            game.finish();
         }
+        game
     }
 
-    pub fn display(&self) {
-        let total_line_length = 2 * self.board.holes() + 5;
+    pub fn set_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observer = observer;
+    }
 
-        // print north name
-        let shift_amt = (total_line_length - self.north.get_name().len() as i32) / 2;
-        for _ in 0..shift_amt {
-            print!(" ");
-        }
-        println!("{}", self.north.get_name());
+    pub fn set_pie_rule(&mut self, enabled: bool) {
+        self.pie_rule = enabled;
+    }
 
-        // print north holes
-        print!("   ");
-        for hole in 1..=self.board.holes() {
-            print!("{}", self.board.beans(Side::North, hole));
-            print!(" ");
+    pub fn set_illegal_move_policy(&mut self, policy: IllegalMovePolicy) {
+        if let IllegalMovePolicy::RandomMove { seed } = policy {
+            self.rng = Rng::new(seed);
         }
-        println!();
+        self.illegal_move_policy = policy;
+    }
 
-        // print both players' pots
-        print!(" ");
-        print!("{} ", self.board.beans(Side::North, 0));
-        for _ in 1..=self.board.holes() {
-            print!("  ")
-        }
-        println!("{} ", self.board.beans(Side::South, 0));
+    pub fn set_adjudication(&mut self, adjudication: Adjudication) {
+        self.adjudication = adjudication;
+    }
+
+    // makes the game a timed one: the side to move's clock starts now, and a side that runs out
+    // of time loses. The clock keeps running through extra turns, and in the reply to the first
+    // move under the pie rule, through the choice to swap.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+        self.restart_clock();
+    }
+
+    pub fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
 
-        // print south holes
-        print!("   ");
-        for hole in 1..=self.board.holes() {
-            print!("{}", self.board.beans(Side::South, hole));
-            print!(" ");
+    // ends the game if the side to move has run out of time; returns true if it has. Moves
+    // submitted after that are refused, but the game only notices when asked, so something
+    // driving the game from outside should call this while it waits for a move.
+    pub fn check_clock(&mut self) -> bool {
+        if self.is_over {
+            return false;
         }
-        println!();
+        match self.clock.as_ref().and_then(Clock::flag_fallen) {
+            Some(side) => {
+                self.end_early(Some(side.opponent()), Termination::TimeForfeit(side));
+                true
+            }
+            None => false,
+        }
+    }
 
-        // print south name
-        let shift_amt = (total_line_length - self.south.get_name().len() as i32) / 2;
-        for _ in 0..shift_amt {
-            print!(" ");
+    // starts the clock of the side to move, or stops the clocks once the game is over.
+    fn restart_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            if self.is_over {
+                clock.stop();
+            } else {
+                clock.start(self.side_to_move);
+            }
         }
-        println!("{}", self.south.get_name());
     }
 
-    fn status(&self) -> GameStatus {
+    // true if the players swapped sides under the pie rule.
+    pub fn sides_swapped(&self) -> bool {
+        self.sides_swapped
+    }
+
+    // tells the observer about event, showing it the game as it is now.
+    fn notify(&mut self, event: GameEvent) {
+        let south = self.south.get_name();
+        let north = self.north.get_name();
+        let view = GameView {
+            board: &self.board,
+            south: &south,
+            north: &north,
+            clock: self.clock.as_ref(),
+        };
+        self.observer.notify(&view, &event);
+    }
+
+    pub fn status(&self) -> GameStatus {
         GameStatus {
             over: self.is_over,
             winner: self.winner,
+            termination: self.termination,
+            south_score: self.board.beans(Side::South, 0),
+            north_score: self.board.beans(Side::North, 0),
+            side_to_move: self.side_to_move,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    // the moves the side to move can make now; none once the game is over.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        if self.is_over {
+            return Vec::new();
+        }
+        self.board.legal_moves(self.side_to_move).collect()
+    }
+
+    // plays mv for the side to move: one sowing, which finishes the side's complete move unless
+    // it earns another turn, in which case the same side submits again. The observer hears of
+    // any capture, extra turn or sweep. In a timed game, a move made once the side's time has
+    // run out is refused and the game ends.
+    pub fn submit_move(&mut self, mv: Move) -> Result<TurnOutcome, GameError> {
+        if self.is_over {
+            return Err(GameError::Over);
+        }
+        if self.check_clock() {
+            return Err(GameError::OutOfTime);
+        }
+        let s = self.side_to_move;
+        let undo = self
+            .board
+            .make_move(s, mv)
+            .map_err(GameError::IllegalMove)?;
+        let outcome = undo.outcome;
+        if self.turn_undos.is_empty() {
+            // a new move: whatever was undone before it can no longer be redone.
+            self.undone.clear();
+        }
+        self.turn_sowings.push(Sowing {
+            mv,
+            captured: outcome.captured,
+        });
+        self.turn_undos.push(undo);
+        if outcome.captured > 0 {
+            self.notify(GameEvent::Captured {
+                side: s,
+                beans: outcome.captured,
+            });
+        }
+
+        let turn_complete = !outcome.extra_turn || outcome.game_over();
+        if turn_complete {
+            let record = MoveRecord {
+                side: s,
+                sowings: std::mem::take(&mut self.turn_sowings),
+            };
+            let undos = std::mem::take(&mut self.turn_undos);
+            self.push_move(record, undos);
+            self.side_to_move = outcome.next_to_move;
+            if let (Some(clock), false) = (&mut self.clock, outcome.game_over()) {
+                clock.press();
+            }
+            // by moving, s turns down any draw its opponent offered.
+            if self.draw_offer == Some(s.opponent()) {
+                self.draw_offer = None;
+            }
+        }
+        if let Some(sweep) = outcome.sweep {
+            self.notify(GameEvent::Swept(sweep));
+            self.final_sweep = Some(sweep);
+            self.finish();
+        } else if !self.adjudicate() && outcome.extra_turn {
+            self.notify(GameEvent::ExtraTurn { side: s });
+        }
+
+        Ok(TurnOutcome {
+            side: s,
+            mv,
+            outcome,
+            turn_complete,
+            status: self.status(),
+        })
+    }
+
+    // ends the game if the Adjudication says it is decided; returns true if it is.
+    fn adjudicate(&mut self) -> bool {
+        let south = self.board.beans(Side::South, 0);
+        let north = self.board.beans(Side::North, 0);
+        let half = self.board.total_beans() / 2;
+        if self.adjudication.majority && (south > half || north > half) {
+            let winner = if south > north {
+                Side::South
+            } else {
+                Side::North
+            };
+            self.end_early(Some(winner), Termination::Majority);
+        } else if self
+            .adjudication
+            .move_limit
+            .is_some_and(|limit| self.moves.len() >= limit)
+            && self.turn_undos.is_empty()
+        {
+            let winner = match south.cmp(&north) {
+                Ordering::Equal => None,
+                Ordering::Greater => Some(Side::South),
+                Ordering::Less => Some(Side::North),
+            };
+            self.end_early(winner, Termination::MoveLimit);
+        }
+        self.is_over
+    }
+
+    // side gives up the game, whether or not it is its turn.
+    pub fn resign(&mut self, side: Side) -> Result<(), GameError> {
+        if self.is_over {
+            return Err(GameError::Over);
+        }
+        self.end_early(Some(side.opponent()), Termination::Resignation(side));
+        Ok(())
+    }
+
+    // side offers its opponent a draw, which stands until the opponent accepts it or makes a
+    // complete move.
+    pub fn offer_draw(&mut self, side: Side) -> Result<(), GameError> {
+        if self.is_over {
+            return Err(GameError::Over);
+        }
+        self.draw_offer = Some(side);
+        self.notify(GameEvent::DrawOffered { side });
+        Ok(())
+    }
+
+    // the side whose draw offer is waiting for an answer, if any.
+    pub fn draw_offer(&self) -> Option<Side> {
+        self.draw_offer
+    }
+
+    // side accepts the draw its opponent offered, which ends the game.
+    pub fn accept_draw(&mut self, side: Side) -> Result<(), GameError> {
+        if self.is_over {
+            return Err(GameError::Over);
+        }
+        if self.draw_offer != Some(side.opponent()) {
+            return Err(GameError::NoDrawOffer);
         }
+        self.end_early(None, Termination::DrawAgreed);
+        Ok(())
+    }
+
+    // true if the side to move may swap sides under the pie rule instead of replying to the
+    // first move.
+    pub fn swap_available(&self) -> bool {
+        self.pie_rule
+            && !self.is_over
+            && !self.sides_swapped
+            && self.moves.len() == 1
+            && self.turn_undos.is_empty()
+    }
+
+    // under the pie rule, the player to reply to the first move takes over the side that made it
+    // (and that position) instead, and the player who made it goes on to move next from the
+    // other side.
+    pub fn swap_sides(&mut self) -> Result<(), GameError> {
+        if !self.swap_available() {
+            return Err(GameError::SwapNotAllowed);
+        }
+        std::mem::swap(&mut self.north, &mut self.south);
+        self.sides_swapped = true;
+        self.notify(GameEvent::SwapOffered {
+            side: self.side_to_move.opponent(),
+            swapped: true,
+        });
+        Ok(())
     }
 
     fn get_player(&self, s: Side) -> &dyn Player {
@@ -89,110 +497,1305 @@ impl Game {
         // Attempt to make a complete move for the player playing side s.
         // "Complete" means that the player sows the seeds from a hole and takes any additional
         // turns required or completes a capture. Whenever the player gets an additional turn,
-        // you should display the board so someone looking at the screen can follow what's
-        // happening. If the move can be completed, return true; if not, because the move is not
-        // yet completed but side s has no holes with beans to pick up and sow, sweep any beans
-        // in s's opponent's holes into that opponent's pot and return false.
-
-        let player = match s {
-            Side::North => &self.north,
-            Side::South => &self.south,
-        };
+        // the observer is told so someone looking at the screen can follow what's
+        // happening. If the move can be completed and the game goes on, return true. If the
+        // move leaves the side to move next with nothing to sow, the remaining beans are swept
+        // (see Board::apply_move) and we return false. So we do if the player forfeits.
+        assert_eq!(s, self.side_to_move);
 
+        // the moves rejected so far in this complete move
+        let mut rejections = 0;
         loop {
-            let move_chosen = player.choose_move(&self.board, s);
-
-            if move_chosen == -1 {
-                // indicates no move is possible, and so sweep beans into s's opponent's holes and return false.
+            let turn = match self.play_chosen_move(s, &mut rejections) {
+                Some(turn) => turn,
+                None => return false,
+            };
+            if turn.status.over {
+                return false;
+            }
+            if turn.turn_complete {
+                return true;
+            }
+        }
+    }
 
-                println!(
-                    "{} has no beans left to sow.",
-                    self.get_player(s).get_name()
-                );
-                println!(
-                    "Sweeping remaining beans into {}'s pot.",
-                    self.get_player(s.opponent()).get_name()
-                );
+    // asks the player on side s for a move and plays it, applying the IllegalMovePolicy until a
+    // legal move has been played. Returns None if the game ends first, with the player
+    // forfeiting or running out of time.
+    fn play_chosen_move(&mut self, s: Side, rejections: &mut u32) -> Option<TurnOutcome> {
+        loop {
+            self.tell_time_left(s);
+            let player = self.get_player(s);
+            match player.choose_move(&self.board, s) {
+                Some(mv) => {
+                    let explanation = player.explain_choice();
+                    self.notify(GameEvent::MoveChosen {
+                        side: s,
+                        mv,
+                        explanation,
+                    });
+                    match self.submit_move(mv) {
+                        Ok(turn) => return Some(turn),
+                        Err(GameError::IllegalMove(error)) => {
+                            self.notify(GameEvent::MoveRejected { side: s, mv, error })
+                        }
+                        Err(GameError::OutOfTime) => return None,
+                        Err(error) => {
+                            panic!("{} cannot move: {}", self.get_player(s).get_name(), error)
+                        }
+                    }
+                }
+                None => self.notify(GameEvent::NoMoveChosen { side: s }),
+            }
 
-                for hole in 1..=self.board.holes() {
-                    self.board.move_to_pot(s.opponent(), hole, s.opponent());
+            *rejections += 1;
+            match self.illegal_move_policy {
+                IllegalMovePolicy::AskAgain(retries) if *rejections <= retries => {}
+                IllegalMovePolicy::RandomMove { .. } => {
+                    let moves = self.legal_moves();
+                    let mv = moves[self.rng.range(0, moves.len() as i32 - 1) as usize];
+                    self.notify(GameEvent::MoveSubstituted { side: s, mv });
+                    return self.submit_move(mv).ok();
+                }
+                _ => {
+                    self.end_early(Some(s.opponent()), Termination::IllegalMove(s));
+                    return None;
                 }
+            }
+        }
+    }
 
-                self.is_over = true;
+    // adds a move just played to the record. Anything undone before it can no longer be redone.
+    fn push_move(&mut self, record: MoveRecord, undos: Vec<Undo>) {
+        self.moves.push(record);
+        self.undos.push(undos);
+        self.undone.clear();
+    }
 
-                let north_pot_beans = self.board.beans(Side::North, 0);
-                let south_pot_beans = self.board.beans(Side::South, 0);
+    // takes back the last complete move, with every extra turn in it and the end of the game if
+    // it came with it, so that the side that made it is to move again. Undoing the first move
+    // also undoes a swap under the pie rule. A move left unfinished by an extra turn is taken
+    // back (for good) instead. A game ended without a move, by a resignation, an agreed draw or
+    // a forfeit, only has its ending taken back, with any move it cut short still in progress.
+    // Returns false if there is nothing to take back.
+    pub fn undo(&mut self) -> bool {
+        if let Some(
+            Termination::IllegalMove(_)
+            | Termination::TimeForfeit(_)
+            | Termination::Resignation(_)
+            | Termination::DrawAgreed,
+        ) = self.termination
+        {
+            let unfinished = self
+                .undos
+                .last()
+                .and_then(|undos| undos.last())
+                .is_some_and(|undo| undo.outcome.extra_turn && undo.outcome.sweep.is_none());
+            if unfinished {
+                // end_early recorded the move the game ended in the middle of; it goes on.
+                let record = self.moves.pop().unwrap();
+                self.turn_sowings = record.sowings;
+                self.turn_undos = self.undos.pop().unwrap();
+            }
+            self.is_over = false;
+            self.winner = None;
+            self.termination = None;
+            self.restart_clock();
+            return true;
+        }
 
-                self.winner = match north_pot_beans.cmp(&south_pot_beans) {
-                    Ordering::Equal => None,
-                    Ordering::Greater => Some(Side::North),
-                    Ordering::Less => Some(Side::South),
-                };
+        if !self.turn_undos.is_empty() {
+            for undo in self.turn_undos.iter().rev() {
+                self.board.unmake_move(undo);
+            }
+            self.turn_undos.clear();
+            self.turn_sowings.clear();
+            self.restart_clock();
+            return true;
+        }
 
-                self.display();
+        let undos = match self.undos.pop() {
+            Some(undos) => undos,
+            None => return false,
+        };
+        let record = self.moves.pop().unwrap();
+        for undo in undos.iter().rev() {
+            self.board.unmake_move(undo);
+        }
+        let side_after = self.side_to_move;
+        let ending = self
+            .termination
+            .map(|termination| (self.winner, termination));
 
-                return false;
-            }
+        self.is_over = false;
+        self.winner = None;
+        self.termination = None;
+        self.draw_offer = None;
+        self.final_sweep = None;
 
-            let mut end_side: Side = Side::North;
-            let mut end_hole: i32 = 0;
+        let swapped = self.moves.is_empty() && self.sides_swapped;
+        if swapped {
+            std::mem::swap(&mut self.north, &mut self.south);
+            self.sides_swapped = false;
+        }
 
-            self.board.sow(s, move_chosen, &mut end_side, &mut end_hole);
+        self.side_to_move = record.side;
+        self.undone.push(Undone {
+            record,
+            swapped,
+            side_to_move: side_after,
+            ending,
+        });
+        self.restart_clock();
+        true
+    }
 
-            // execute a capture if the move ends in a player's own empty hole, and the opponent's hole
-            // opposite the ending hole is nonempty:
-            #[allow(clippy::collapsible_if)]
-            if end_hole != 0 && end_side == s {
-                if self.board.beans(s, end_hole) == 1 {
-                    // if the hole used to be empty
-                    if self.board.beans(s.opponent(), end_hole) != 0 {
-                        // if the opponent's corresponding hole is nonempty, execute capture
-                        self.board.move_to_pot(s, end_hole, s);
-                        self.board.move_to_pot(s.opponent(), end_hole, s);
-                    }
-                }
-            }
+    // plays the last move taken back by undo again. Returns false if there is none.
+    pub fn redo(&mut self) -> bool {
+        let Undone {
+            record,
+            swapped,
+            side_to_move,
+            ending,
+        } = match self.undone.pop() {
+            Some(undone) => undone,
+            None => return false,
+        };
 
-            if end_hole == 0 {
-                // if further move is needed because move ends at pot:
-                self.display();
-                println!("{} gets another turn", self.get_player(s).get_name());
-                continue;
-            } else {
-                // no further move needed:
-                break;
-            }
+        let mut undos = Vec::new();
+        for sowing in record.sowings.iter() {
+            let undo = self
+                .board
+                .make_move(record.side, sowing.mv)
+                .expect("an undone move can be made again");
+            undos.push(undo);
         }
+        let outcome = undos.last().unwrap().outcome;
+        self.side_to_move = side_to_move;
+        self.moves.push(record);
+        self.undos.push(undos);
 
+        if swapped {
+            std::mem::swap(&mut self.north, &mut self.south);
+            self.sides_swapped = true;
+        }
+        // the game ends again as it did, whether played out or adjudicated.
+        self.final_sweep = outcome.sweep;
+        if let Some((winner, termination)) = ending {
+            self.is_over = true;
+            self.winner = winner;
+            self.termination = Some(termination);
+        }
+        self.restart_clock();
         true
     }
 
+    pub fn side_to_move(&self) -> Side {
+        self.side_to_move
+    }
+
+    // the record of the game so far, with the players named by the side they started on.
+    pub fn record(&self) -> GameRecord {
+        let (south, north) = if self.sides_swapped {
+            (&self.north, &self.south)
+        } else {
+            (&self.south, &self.north)
+        };
+
+        GameRecord {
+            south: south.get_name(),
+            north: north.get_name(),
+            rules: self.board.rules(),
+            start: self.start.clone(),
+            first_to_move: self.first_to_move,
+            swapped: self.sides_swapped,
+            moves: self.moves.clone(),
+            sweep: self.final_sweep,
+            result: if self.is_over {
+                Some(self.winner)
+            } else {
+                None
+            },
+            termination: self.termination,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        self.record().save(path)
+    }
+
+    // records the game as over and works out the winner from the pots.
+    fn finish(&mut self) {
+        self.is_over = true;
+        self.termination = Some(Termination::Normal);
+        self.restart_clock();
+
+        let north_pot_beans = self.board.beans(Side::North, 0);
+        let south_pot_beans = self.board.beans(Side::South, 0);
+
+        self.winner = match north_pot_beans.cmp(&south_pot_beans) {
+            Ordering::Equal => None,
+            Ordering::Greater => Some(Side::North),
+            Ordering::Less => Some(Side::South),
+        };
+    }
+
+    // ends the game before it has been played out, whatever the pots say. A move left
+    // unfinished by an extra turn goes into the record as it stands.
+    fn end_early(&mut self, winner: Option<Side>, termination: Termination) {
+        if !self.turn_undos.is_empty() {
+            let record = MoveRecord {
+                side: self.side_to_move,
+                sowings: std::mem::take(&mut self.turn_sowings),
+            };
+            let undos = std::mem::take(&mut self.turn_undos);
+            self.push_move(record, undos);
+        }
+        self.is_over = true;
+        self.winner = winner;
+        self.termination = Some(termination);
+        self.draw_offer = None;
+        self.restart_clock();
+    }
+
+    // lets the player on side s know how much time it has left, in a timed game, before it is
+    // asked to decide anything.
+    fn tell_time_left(&self, s: Side) {
+        if let Some(clock) = &self.clock {
+            self.get_player(s)
+                .set_time_left(clock.control(), clock.remaining(s));
+        }
+    }
+
+    // offers the player replying to the first move the chance to swap sides. If they take it,
+    // the two players trade places: each takes over the other's side, name and all, and the
+    // player who made the first move goes on to move next from the other side.
+    fn offer_swap(&mut self, side_to_reply: Side) {
+        self.tell_time_left(side_to_reply);
+        let player = self.get_player(side_to_reply);
+        if player.should_swap(&self.board, side_to_reply) {
+            self.swap_sides().expect("a swap is available");
+        } else {
+            self.notify(GameEvent::SwapOffered {
+                side: side_to_reply.opponent(),
+                swapped: false,
+            });
+        }
+    }
+
+    // asks the player on side s, whose turn it is, whether it resigns or offers a draw, and its
+    // opponent whether it accepts the draw. Returns true if the game is over.
+    fn resign_or_draw(&mut self, s: Side) -> bool {
+        self.tell_time_left(s);
+        let player = self.get_player(s);
+        if player.should_resign(&self.board, s) {
+            self.resign(s).expect("the game is not over");
+            return true;
+        }
+        if player.offers_draw(&self.board, s) {
+            self.offer_draw(s).expect("the game is not over");
+            let opponent = s.opponent();
+            self.tell_time_left(opponent);
+            if self
+                .get_player(opponent)
+                .accepts_draw(&self.board, opponent)
+            {
+                self.accept_draw(opponent).expect("a draw was offered");
+                return true;
+            }
+            self.draw_offer = None;
+            self.notify(GameEvent::DrawDeclined { side: opponent });
+        }
+        false
+    }
+
     pub fn play(&mut self) {
-        let mut side_to_move = Side::South;
-        loop {
-            self.display();
-            let res = self.make_move(side_to_move);
+        if let (true, Some(sweep)) = (self.moves.is_empty(), self.final_sweep) {
+            // the game started from a position in which the side to move could not sow.
+            self.notify(GameEvent::Swept(sweep));
+        }
+        while !self.is_over {
+            self.notify(GameEvent::TurnStarted {
+                side: self.side_to_move,
+            });
+            if self.resign_or_draw(self.side_to_move) {
+                break;
+            }
+            let res = self.make_move(self.side_to_move);
             if !res {
                 break;
             }
 
-            side_to_move = side_to_move.opponent();
+            if self.swap_available() {
+                self.offer_swap(self.side_to_move);
+            }
         }
 
-        match self.winner {
-            Some(Side::North) => {
-                println!("The winner is {}.", self.north.get_name())
-            }
-            Some(Side::South) => {
-                println!("The winner is {}.", self.south.get_name())
+        self.notify(GameEvent::GameOver {
+            winner: self.winner,
+            sides_swapped: self.sides_swapped,
+            termination: self.termination.expect("the game is over"),
+        });
+    }
+
+    fn beans(&self, s: Side, hole: i32) -> i32 {
+        self.board.beans(s, hole)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::clock::{ManualTime, TimeControl};
+    use crate::observer::SilentObserver;
+    use crate::rules::Rules;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    // plays the first legal move, and answers the pie rule with a fixed decision (panicking
+    // if it is asked when it should not be).
+    struct TestPlayer {
+        name: String,
+        swap: Option<bool>,
+    }
+
+    impl TestPlayer {
+        fn boxed(name: &str, swap: Option<bool>) -> Box<dyn Player> {
+            Box::new(TestPlayer {
+                name: name.to_owned(),
+                swap,
+            })
+        }
+    }
+
+    impl Player for TestPlayer {
+        fn get_name(&self) -> String {
+            self.name.clone()
+        }
+
+        fn is_interactive(&self) -> bool {
+            false
+        }
+
+        fn choose_move(&self, b: &Board, s: Side) -> Option<Move> {
+            b.legal_moves(s).next()
+        }
+
+        fn should_swap(&self, _b: &Board, _s: Side) -> bool {
+            self.swap.expect("should_swap called unexpectedly")
+        }
+    }
+
+    // always chooses the same move, legal or not, or no move at all.
+    struct Stubborn {
+        choice: Option<Move>,
+    }
+
+    impl Player for Stubborn {
+        fn get_name(&self) -> String {
+            "Stubborn".to_owned()
+        }
+
+        fn is_interactive(&self) -> bool {
+            false
+        }
+
+        fn choose_move(&self, _b: &Board, _s: Side) -> Option<Move> {
+            self.choice
+        }
+    }
+
+    // plays Stubborn on South against a TestPlayer under policy.
+    fn stubborn_game(
+        board: Board,
+        choice: Option<Move>,
+        policy: IllegalMovePolicy,
+    ) -> (Game, Vec<GameEvent>) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut game = Game::new(
+            board,
+            Box::new(Stubborn { choice }),
+            TestPlayer::boxed("Other", None),
+        );
+        game.set_illegal_move_policy(policy);
+        game.set_observer(Box::new(Recorder {
+            events: events.clone(),
+        }));
+        game.play();
+        let events = events.borrow().clone();
+        (game, events)
+    }
+
+    // plays the first legal move after thinking for a while by a ManualTime.
+    struct Slow {
+        time: ManualTime,
+        thinking: Duration,
+    }
+
+    impl Player for Slow {
+        fn get_name(&self) -> String {
+            "Slow".to_owned()
+        }
+
+        fn is_interactive(&self) -> bool {
+            false
+        }
+
+        fn choose_move(&self, b: &Board, s: Side) -> Option<Move> {
+            self.time.advance(self.thinking);
+            b.legal_moves(s).next()
+        }
+    }
+
+    // plays the first legal move, and resigns, offers or accepts draws as told.
+    struct Diplomat {
+        resigns: bool,
+        offers: bool,
+        accepts: bool,
+    }
+
+    impl Player for Diplomat {
+        fn get_name(&self) -> String {
+            "Diplomat".to_owned()
+        }
+
+        fn is_interactive(&self) -> bool {
+            false
+        }
+
+        fn choose_move(&self, b: &Board, s: Side) -> Option<Move> {
+            b.legal_moves(s).next()
+        }
+
+        fn should_resign(&self, _b: &Board, _s: Side) -> bool {
+            self.resigns
+        }
+
+        fn offers_draw(&self, _b: &Board, _s: Side) -> bool {
+            self.offers
+        }
+
+        fn accepts_draw(&self, _b: &Board, _s: Side) -> bool {
+            self.accepts
+        }
+    }
+
+    // plays the first legal move, and accepts a draw only once it has been told its time.
+    struct Punctual {
+        time_left: Cell<Option<Duration>>,
+    }
+
+    impl Player for Punctual {
+        fn get_name(&self) -> String {
+            "Punctual".to_owned()
+        }
+
+        fn is_interactive(&self) -> bool {
+            false
+        }
+
+        fn choose_move(&self, b: &Board, s: Side) -> Option<Move> {
+            b.legal_moves(s).next()
+        }
+
+        fn accepts_draw(&self, _b: &Board, _s: Side) -> bool {
+            self.time_left.get().is_some()
+        }
+
+        fn set_time_left(&self, _control: TimeControl, remaining: Duration) {
+            self.time_left.set(Some(remaining));
+        }
+    }
+
+    // plays two Diplomats, South (resigning or offering draws) against North (accepting them or
+    // not).
+    fn diplomatic_game(resigns: bool, offers: bool, accepts: bool) -> (Game, Vec<GameEvent>) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut game = Game::new(
+            Board::new(4, 3),
+            Box::new(Diplomat {
+                resigns,
+                offers,
+                accepts: false,
+            }),
+            Box::new(Diplomat {
+                resigns: false,
+                offers: false,
+                accepts,
+            }),
+        );
+        game.set_observer(Box::new(Recorder {
+            events: events.clone(),
+        }));
+        game.play();
+        let events = events.borrow().clone();
+        (game, events)
+    }
+
+    // collects every event the game tells it about.
+    struct Recorder {
+        events: Rc<RefCell<Vec<GameEvent>>>,
+    }
+
+    impl GameObserver for Recorder {
+        fn notify(&mut self, _view: &GameView, event: &GameEvent) {
+            self.events.borrow_mut().push(event.clone());
+        }
+    }
+
+    fn recorded_game(board: Board, pie_rule: bool) -> (Game, Vec<GameEvent>) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut game = Game::new(
+            board,
+            TestPlayer::boxed("First", None),
+            TestPlayer::boxed("Second", Some(true)),
+        );
+        game.set_pie_rule(pie_rule);
+        game.set_observer(Box::new(Recorder {
+            events: events.clone(),
+        }));
+        game.play();
+        let events = events.borrow().clone();
+        (game, events)
+    }
+
+    #[test]
+    fn observers_hear_the_whole_game() {
+        let (_, events) = recorded_game(Board::new(2, 1), false);
+        let chose = |side, hole| GameEvent::MoveChosen {
+            side,
+            mv: Move::new(hole).unwrap(),
+            explanation: None,
+        };
+        assert_eq!(
+            events,
+            vec![
+                GameEvent::TurnStarted { side: Side::South },
+                chose(Side::South, 1),
+                GameEvent::TurnStarted { side: Side::North },
+                chose(Side::North, 1),
+                GameEvent::ExtraTurn { side: Side::North },
+                chose(Side::North, 2),
+                GameEvent::TurnStarted { side: Side::South },
+                chose(Side::South, 2),
+                GameEvent::TurnStarted { side: Side::North },
+                chose(Side::North, 1),
+                GameEvent::ExtraTurn { side: Side::North },
+                chose(Side::North, 2),
+                GameEvent::Swept(Sweep {
+                    empty_side: Side::South,
+                    from_side: Side::North,
+                    pot_owner: Side::North,
+                    beans: 1,
+                }),
+                GameEvent::GameOver {
+                    winner: Some(Side::North),
+                    sides_swapped: false,
+                    termination: Termination::Normal,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn observers_hear_captures_and_swaps() {
+        let board = Board::from_contents(&[0, 5, 0], &[1, 0, 0], 0, 0, Rules::default()).unwrap();
+        let (_, events) = recorded_game(board, false);
+        assert_eq!(
+            events[2],
+            GameEvent::Captured {
+                side: Side::South,
+                beans: 6
             }
-            None => {
-                println!("The game is a tie.")
+        );
+
+        let (game, events) = recorded_game(Board::new(3, 2), true);
+        assert!(game.sides_swapped());
+        assert!(events.contains(&GameEvent::SwapOffered {
+            side: Side::South,
+            swapped: true
+        }));
+        assert!(matches!(
+            events.last(),
+            Some(GameEvent::GameOver {
+                sides_swapped: true,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn pie_rule_swap_trades_sides() {
+        let mut game = Game::new(
+            Board::new(3, 2),
+            TestPlayer::boxed("First", None),
+            TestPlayer::boxed("Second", Some(true)),
+        );
+        game.set_pie_rule(true);
+        game.play();
+
+        assert!(game.sides_swapped());
+        assert_eq!(game.south.get_name(), "Second");
+        assert_eq!(game.north.get_name(), "First");
+        assert!(game.is_over);
+    }
+
+    #[test]
+    fn pie_rule_swap_can_be_declined() {
+        let mut game = Game::new(
+            Board::new(3, 2),
+            TestPlayer::boxed("First", None),
+            TestPlayer::boxed("Second", Some(false)),
+        );
+        game.set_pie_rule(true);
+        game.play();
+
+        assert!(!game.sides_swapped());
+        assert_eq!(game.south.get_name(), "First");
+        assert!(game.is_over);
+    }
+
+    #[test]
+    fn no_swap_offered_without_pie_rule() {
+        let mut game = Game::new(
+            Board::new(3, 2),
+            TestPlayer::boxed("First", None),
+            TestPlayer::boxed("Second", None),
+        );
+        game.play();
+
+        assert!(!game.sides_swapped());
+        assert!(game.is_over);
+    }
+
+    // a position in which whoever moves first captures and wins at once.
+    fn first_mover_wins() -> Board {
+        let mut b = Board::new(2, 0);
+        b.set_beans(Side::North, 2, 1).unwrap();
+        b.set_beans(Side::South, 1, 1).unwrap();
+        b
+    }
+
+    #[test]
+    fn south_moves_first_by_default() {
+        let mut game = Game::new(
+            first_mover_wins(),
+            TestPlayer::boxed("First", None),
+            TestPlayer::boxed("Second", None),
+        );
+        game.play();
+        assert_eq!(game.winner, Some(Side::South));
+    }
+
+    #[test]
+    fn game_from_position_with_north_to_move() {
+        let mut game = Game::new_from_position(
+            first_mover_wins(),
+            Side::North,
+            TestPlayer::boxed("First", None),
+            TestPlayer::boxed("Second", None),
+        );
+        game.play();
+        assert_eq!(game.winner, Some(Side::North));
+        assert_eq!(game.board.beans(Side::North, 0), 2);
+    }
+
+    #[test]
+    fn game_from_finished_position_ends_at_once() {
+        let mut b = Board::new(2, 0);
+        b.set_beans(Side::South, 1, 3).unwrap();
+        let mut game = Game::new_from_position(
+            b,
+            Side::North,
+            TestPlayer::boxed("First", None),
+            TestPlayer::boxed("Second", None),
+        );
+        game.play();
+        assert_eq!(game.winner, Some(Side::South));
+        assert_eq!(game.board.beans(Side::South, 0), 3);
+    }
+
+    #[test]
+    fn undo_and_redo_whole_moves() {
+        let mut game = Game::new(
+            Board::new(4, 3),
+            TestPlayer::boxed("First", None),
+            TestPlayer::boxed("Second", None),
+        );
+        game.play();
+        let final_board = game.board.clone();
+        let winner = game.winner;
+        let num_moves = game.moves.len();
+        assert!(num_moves > 1);
+
+        assert!(game.undo());
+        assert!(!game.is_over);
+        assert_eq!(game.side_to_move(), game.undone[0].record.side);
+        while game.undo() {}
+        assert_eq!(game.board, game.start);
+        assert_eq!(game.side_to_move(), Side::South);
+        assert!(game.moves.is_empty());
+
+        while game.redo() {}
+        assert_eq!(game.board, final_board);
+        assert_eq!(game.moves.len(), num_moves);
+        assert!(game.is_over);
+        assert_eq!(game.winner, winner);
+    }
+
+    #[test]
+    fn a_new_move_after_undo_drops_the_redo_history() {
+        let mut game = Game::new(
+            Board::new(4, 3),
+            TestPlayer::boxed("First", None),
+            TestPlayer::boxed("Second", None),
+        );
+        game.play();
+        assert!(game.undo());
+        assert!(game.undo());
+
+        // playing on from here is the same as redoing, but forgets what was undone.
+        let side = game.side_to_move();
+        assert!(game.make_move(side));
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn undoing_the_first_move_undoes_a_swap() {
+        let mut game = Game::new(
+            Board::new(3, 2),
+            TestPlayer::boxed("First", None),
+            TestPlayer::boxed("Second", Some(true)),
+        );
+        game.set_pie_rule(true);
+        game.play();
+        assert!(game.sides_swapped());
+
+        while game.undo() {}
+        assert!(!game.sides_swapped());
+        assert_eq!(game.south.get_name(), "First");
+
+        assert!(game.redo());
+        assert!(game.sides_swapped());
+        assert_eq!(game.south.get_name(), "Second");
+        assert_eq!(game.side_to_move(), Side::North);
+    }
+
+    #[test]
+    fn a_driven_game_is_played_one_sowing_at_a_time() {
+        let mv = |hole| Move::new(hole).unwrap();
+        let mut game = Game::new_driven(Board::new(2, 1), Side::South, "Ann", "Bob");
+        game.set_observer(Box::new(SilentObserver));
+        assert_eq!(
+            game.status(),
+            GameStatus {
+                over: false,
+                winner: None,
+                termination: None,
+                south_score: 0,
+                north_score: 0,
+                side_to_move: Side::South,
             }
+        );
+        assert_eq!(game.legal_moves(), vec![mv(1), mv(2)]);
+        assert!(matches!(
+            game.submit_move(mv(3)),
+            Err(GameError::IllegalMove(_))
+        ));
+
+        let turn = game.submit_move(mv(1)).unwrap();
+        assert!(turn.turn_complete);
+        assert_eq!(turn.status.side_to_move, Side::North);
+
+        // North's first sowing ends in its pot, so the turn goes on, and undo takes it back.
+        let turn = game.submit_move(mv(1)).unwrap();
+        assert!(turn.outcome.extra_turn && !turn.turn_complete);
+        assert_eq!(turn.status.north_score, 1);
+        assert_eq!(game.side_to_move(), Side::North);
+        assert!(game.undo());
+        assert_eq!(game.status().north_score, 0);
+        assert_eq!(game.record().moves.len(), 1);
+
+        for (hole, complete) in [(1, false), (2, true), (2, true), (1, false), (2, true)] {
+            assert_eq!(game.submit_move(mv(hole)).unwrap().turn_complete, complete);
         }
+        let status = game.status();
+        assert!(status.over);
+        assert_eq!(status.winner, Some(Side::North));
+        assert_eq!(status.south_score + status.north_score, 4);
+        assert!(game.legal_moves().is_empty());
+        assert_eq!(game.submit_move(mv(1)), Err(GameError::Over));
+
+        // the same game as played by players who pick the first legal move.
+        let (played, _) = recorded_game(Board::new(2, 1), false);
+        assert_eq!(game.record().moves, played.record().moves);
     }
 
-    fn beans(&self, s: Side, hole: i32) -> i32 {
-        self.board.beans(s, hole)
+    #[test]
+    fn driven_swaps_follow_the_pie_rule() {
+        let mut game = Game::new_driven(Board::new(3, 2), Side::South, "Ann", "Bob");
+        game.set_observer(Box::new(SilentObserver));
+        assert_eq!(game.swap_sides(), Err(GameError::SwapNotAllowed));
+        game.set_pie_rule(true);
+        assert_eq!(game.swap_sides(), Err(GameError::SwapNotAllowed));
+
+        game.submit_move(Move::new(1).unwrap()).unwrap();
+        assert!(game.swap_available());
+        assert_eq!(game.swap_sides(), Ok(()));
+        assert_eq!(game.south.get_name(), "Bob");
+        assert_eq!(game.side_to_move(), Side::North);
+        assert!(!game.swap_available());
+        assert_eq!(game.swap_sides(), Err(GameError::SwapNotAllowed));
+    }
+
+    #[test]
+    #[should_panic(expected = "submitted to the game")]
+    fn driven_games_cannot_be_played() {
+        let mut game = Game::new_driven(Board::new(3, 2), Side::South, "Ann", "Bob");
+        game.set_observer(Box::new(SilentObserver));
+        game.play();
+    }
+
+    #[test]
+    fn a_bot_that_keeps_choosing_a_bad_hole_does_not_hang_the_game() {
+        // under the default policy the same bad hole is rejected a few times, and then the game
+        // is over.
+        for hole in [3, 99] {
+            let bad_hole = Move::new(hole).unwrap();
+            let (game, events) = stubborn_game(
+                Board::new(2, 1),
+                Some(bad_hole),
+                IllegalMovePolicy::default(),
+            );
+            let rejections = events
+                .iter()
+                .filter(|e| matches!(e, GameEvent::MoveRejected { .. }))
+                .count();
+            assert_eq!(rejections, 4);
+            assert_eq!(
+                game.status().termination,
+                Some(Termination::IllegalMove(Side::South))
+            );
+        }
+    }
+
+    #[test]
+    fn a_player_with_no_move_does_not_hang_the_game() {
+        // choosing nothing counts against the retries like a bad hole, so the default policy
+        // gives up on the player instead of asking it forever.
+        let (game, events) = stubborn_game(Board::new(2, 1), None, IllegalMovePolicy::default());
+        let unanswered = events
+            .iter()
+            .filter(|e| matches!(e, GameEvent::NoMoveChosen { .. }))
+            .count();
+        assert_eq!(unanswered, 4);
+        assert!(game.status().over);
+        assert_eq!(game.status().winner, Some(Side::North));
+        assert_eq!(
+            game.status().termination,
+            Some(Termination::IllegalMove(Side::South))
+        );
+    }
+
+    #[test]
+    fn illegal_moves_forfeit_when_the_retries_run_out() {
+        let off_the_board = Move::new(9).unwrap();
+        let (game, events) = stubborn_game(
+            Board::new(2, 1),
+            Some(off_the_board),
+            IllegalMovePolicy::AskAgain(2),
+        );
+        let rejections = events
+            .iter()
+            .filter(|e| matches!(e, GameEvent::MoveRejected { .. }))
+            .count();
+        assert_eq!(rejections, 3);
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::GameOver {
+                winner: Some(Side::North),
+                sides_swapped: false,
+                termination: Termination::IllegalMove(Side::South),
+            })
+        );
+        let status = game.status();
+        assert_eq!(status.winner, Some(Side::North));
+        assert_eq!(
+            status.termination,
+            Some(Termination::IllegalMove(Side::South))
+        );
+        assert!(game.record().moves.is_empty());
+        assert_eq!(game.record().sweep, None);
+
+        let (_, events) = stubborn_game(Board::new(2, 1), None, IllegalMovePolicy::Forfeit);
+        assert_eq!(
+            events[1..3],
+            [
+                GameEvent::NoMoveChosen { side: Side::South },
+                GameEvent::GameOver {
+                    winner: Some(Side::North),
+                    sides_swapped: false,
+                    termination: Termination::IllegalMove(Side::South),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn a_forfeit_can_end_a_move_half_way() {
+        // hole 2 ends in South's pot, and then it is empty.
+        let hole_2 = Move::new(2).unwrap();
+        let (mut game, _) =
+            stubborn_game(Board::new(2, 1), Some(hole_2), IllegalMovePolicy::Forfeit);
+        let record = game.record();
+        assert_eq!(record.moves.len(), 1);
+        assert_eq!(record.moves[0].sowings.len(), 1);
+        assert_eq!(
+            record.termination,
+            Some(Termination::IllegalMove(Side::South))
+        );
+
+        // the record saves and replays as far as the game went.
+        let text = record.to_string();
+        assert!(text.contains("[Termination \"illegal move by South\"]"));
+        let loaded: GameRecord = text.parse().unwrap();
+        assert_eq!(loaded.termination, record.termination);
+        assert_eq!(&loaded.replay().unwrap(), game.board());
+
+        // undo takes back the forfeit only, and South is in the middle of its move again.
+        assert!(game.undo());
+        assert!(!game.status().over);
+        assert_eq!(game.status().termination, None);
+        assert!(game.record().moves.is_empty());
+        assert_eq!(game.side_to_move(), Side::South);
+        assert_eq!(
+            game.submit_move(hole_2).map(|_| ()),
+            Err(GameError::IllegalMove(BoardError::EmptyHole(2)))
+        );
+        // then the half move goes, for good.
+        assert!(game.undo());
+        assert_eq!(game.board(), &Board::new(2, 1));
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn random_moves_stand_in_for_illegal_ones() {
+        let policy = IllegalMovePolicy::RandomMove { seed: 5 };
+        let (game, events) = stubborn_game(Board::new(4, 3), None, policy);
+        let substitutes = events
+            .iter()
+            .filter(|e| matches!(e, GameEvent::MoveSubstituted { .. }))
+            .count();
+        assert!(substitutes > 0);
+        assert_eq!(game.status().termination, Some(Termination::Normal));
+        assert!(game.record().replay().is_ok());
+
+        // the same seed picks the same moves.
+        let (again, _) = stubborn_game(Board::new(4, 3), None, policy);
+        assert_eq!(again.record().moves, game.record().moves);
+    }
+
+    #[test]
+    fn clocks_run_for_the_side_to_move() {
+        let secs = Duration::from_secs;
+        let mv = |hole| Move::new(hole).unwrap();
+        let time = ManualTime::new();
+        let mut game = Game::new_driven(Board::new(2, 1), Side::South, "Ann", "Bob");
+        game.set_observer(Box::new(SilentObserver));
+        let control = TimeControl::Increment {
+            base: secs(10),
+            increment: secs(1),
+        };
+        game.set_clock(Clock::new(control, Box::new(time.clone())));
+
+        time.advance(secs(3));
+        game.submit_move(mv(1)).unwrap();
+        let clock = game.clock().unwrap();
+        assert_eq!(clock.remaining(Side::South), secs(8));
+        assert_eq!(clock.running(), Some(Side::North));
+
+        // the clock keeps running through an extra turn, and the increment comes at the end.
+        time.advance(secs(2));
+        game.submit_move(mv(1)).unwrap();
+        time.advance(secs(2));
+        game.submit_move(mv(2)).unwrap();
+        assert_eq!(game.clock().unwrap().remaining(Side::North), secs(7));
+
+        // undo gives the turn, and the running clock, back to North.
+        assert!(game.undo());
+        assert_eq!(game.clock().unwrap().running(), Some(Side::North));
+        assert!(game.redo());
+
+        time.advance(secs(7));
+        assert!(!game.check_clock());
+        time.advance(secs(1));
+        assert_eq!(game.submit_move(mv(2)), Err(GameError::OutOfTime));
+        let status = game.status();
+        assert_eq!(status.winner, Some(Side::North));
+        assert_eq!(
+            status.termination,
+            Some(Termination::TimeForfeit(Side::South))
+        );
+        assert_eq!(game.clock().unwrap().running(), None);
+        assert!(game
+            .record()
+            .to_string()
+            .contains("[Termination \"time forfeit by South\"]"));
+    }
+
+    #[test]
+    fn a_slow_player_loses_on_time() {
+        let time = ManualTime::new();
+        let mut game = Game::new(
+            Board::new(6, 4),
+            TestPlayer::boxed("Quick", None),
+            Box::new(Slow {
+                time: time.clone(),
+                thinking: Duration::from_secs(2),
+            }),
+        );
+        game.set_observer(Box::new(SilentObserver));
+        let clock = Clock::new(
+            TimeControl::PerMove(Duration::from_secs(3)),
+            Box::new(time.clone()),
+        );
+        game.set_clock(clock);
+        game.play();
+        // every one of Slow's sowings takes 2 seconds, so it runs out in the first move that
+        // earns it an extra turn.
+        let status = game.status();
+        assert_eq!(
+            status.termination,
+            Some(Termination::TimeForfeit(Side::North))
+        );
+        assert_eq!(status.winner, Some(Side::South));
+        assert!(game.record().replay().is_ok());
+    }
+
+    #[test]
+    fn driven_games_can_be_resigned_or_drawn() {
+        let mv = |hole| Move::new(hole).unwrap();
+        let driven = || {
+            let mut game = Game::new_driven(Board::new(4, 3), Side::South, "Ann", "Bob");
+            game.set_observer(Box::new(SilentObserver));
+            game
+        };
+
+        // an offer stands through the offering side's move, and lapses with the reply.
+        let mut game = driven();
+        assert_eq!(game.accept_draw(Side::North), Err(GameError::NoDrawOffer));
+        game.offer_draw(Side::South).unwrap();
+        assert_eq!(game.accept_draw(Side::South), Err(GameError::NoDrawOffer));
+        game.submit_move(mv(1)).unwrap();
+        assert_eq!(game.draw_offer(), Some(Side::South));
+        game.submit_move(mv(1)).unwrap();
+        assert_eq!(game.draw_offer(), None);
+        assert_eq!(game.accept_draw(Side::North), Err(GameError::NoDrawOffer));
+
+        game.offer_draw(Side::North).unwrap();
+        assert_eq!(game.accept_draw(Side::South), Ok(()));
+        let status = game.status();
+        assert!(status.over);
+        assert_eq!(status.winner, None);
+        assert_eq!(status.termination, Some(Termination::DrawAgreed));
+        assert_eq!(game.offer_draw(Side::South), Err(GameError::Over));
+
+        // either side can resign at any time.
+        let mut game = driven();
+        assert_eq!(game.resign(Side::North), Ok(()));
+        assert_eq!(game.status().winner, Some(Side::South));
+        assert_eq!(
+            game.status().termination,
+            Some(Termination::Resignation(Side::North))
+        );
+        assert_eq!(game.resign(Side::South), Err(GameError::Over));
+        assert!(game
+            .record()
+            .to_string()
+            .contains("[Termination \"resignation by North\"]"));
+    }
+
+    #[test]
+    fn undoing_a_resignation_takes_back_only_the_resignation() {
+        let mv = |hole| Move::new(hole).unwrap();
+        let mut game = Game::new_driven(Board::new(4, 3), Side::South, "Ann", "Bob");
+        game.set_observer(Box::new(SilentObserver));
+        game.submit_move(mv(1)).unwrap();
+        game.submit_move(mv(1)).unwrap();
+        let board = game.board().clone();
+
+        game.resign(Side::South).unwrap();
+        assert!(game.undo());
+        let status = game.status();
+        assert!(!status.over);
+        assert_eq!(status.winner, None);
+        assert_eq!(status.termination, None);
+        assert_eq!(status.side_to_move, Side::South);
+        assert_eq!(game.board(), &board);
+        assert_eq!(game.record().moves.len(), 2);
+
+        // the game goes on, and the next undo takes back North's move as usual.
+        game.offer_draw(Side::South).unwrap();
+        game.accept_draw(Side::North).unwrap();
+        assert!(game.undo());
+        assert_eq!(game.record().moves.len(), 2);
+        assert!(game.undo());
+        assert_eq!(game.side_to_move(), Side::North);
+        assert_eq!(game.record().moves.len(), 1);
+    }
+
+    #[test]
+    fn players_resign_and_offer_draws_at_the_start_of_their_turns() {
+        let (game, events) = diplomatic_game(true, false, false);
+        assert!(game.record().moves.is_empty());
+        assert_eq!(game.status().winner, Some(Side::North));
+        assert_eq!(
+            game.status().termination,
+            Some(Termination::Resignation(Side::South))
+        );
+        assert_eq!(events.len(), 2);
+
+        let (game, events) = diplomatic_game(false, true, true);
+        assert_eq!(game.status().winner, None);
+        assert_eq!(game.status().termination, Some(Termination::DrawAgreed));
+        assert_eq!(events[1], GameEvent::DrawOffered { side: Side::South });
+
+        // a declined offer is made again every turn, and the game is played out.
+        let (game, events) = diplomatic_game(false, true, false);
+        assert_eq!(game.status().termination, Some(Termination::Normal));
+        let declined = events
+            .iter()
+            .filter(|e| **e == GameEvent::DrawDeclined { side: Side::North })
+            .count();
+        let south_moves = game
+            .record()
+            .moves
+            .iter()
+            .filter(|m| m.side == Side::South)
+            .count();
+        assert_eq!(declined, south_moves);
+    }
+
+    #[test]
+    fn players_know_their_time_when_answering_a_draw_offer() {
+        let mut game = Game::new(
+            Board::new(4, 3),
+            Box::new(Diplomat {
+                resigns: false,
+                offers: true,
+                accepts: false,
+            }),
+            Box::new(Punctual {
+                time_left: Cell::new(None),
+            }),
+        );
+        game.set_observer(Box::new(SilentObserver));
+        let control = TimeControl::PerMove(Duration::from_secs(5));
+        game.set_clock(Clock::new(control, Box::new(ManualTime::new())));
+        game.play();
+        assert!(game.record().moves.is_empty());
+        assert_eq!(game.status().termination, Some(Termination::DrawAgreed));
+    }
+
+    #[test]
+    fn decided_games_are_adjudicated() {
+        let played = |adjudication| {
+            let mut game = Game::new(
+                Board::new(5, 4),
+                TestPlayer::boxed("First", None),
+                TestPlayer::boxed("Second", None),
+            );
+            game.set_observer(Box::new(SilentObserver));
+            game.set_adjudication(adjudication);
+            game.play();
+            game
+        };
+        let full = played(Adjudication::default());
+        assert_eq!(full.status().termination, Some(Termination::Normal));
+
+        let game = played(Adjudication {
+            majority: true,
+            move_limit: None,
+        });
+        let status = game.status();
+        assert_eq!(status.termination, Some(Termination::Majority));
+        let winning_pot = status.south_score.max(status.north_score);
+        assert!(winning_pot > 20);
+        assert_eq!(status.winner, full.status().winner);
+        assert!(game.record().moves.len() < full.record().moves.len());
+        let loaded: GameRecord = game.record().to_string().parse().unwrap();
+        assert_eq!(&loaded.replay().unwrap(), game.board());
+
+        let game = played(Adjudication {
+            majority: false,
+            move_limit: Some(3),
+        });
+        let status = game.status();
+        assert_eq!(game.record().moves.len(), 3);
+        assert_eq!(status.termination, Some(Termination::MoveLimit));
+        let expected = match status.south_score.cmp(&status.north_score) {
+            Ordering::Equal => None,
+            Ordering::Greater => Some(Side::South),
+            Ordering::Less => Some(Side::North),
+        };
+        assert_eq!(status.winner, expected);
+    }
+
+    #[test]
+    fn redo_brings_back_an_adjudicated_ending() {
+        let adjudications = [
+            Adjudication {
+                majority: true,
+                move_limit: None,
+            },
+            Adjudication {
+                majority: false,
+                move_limit: Some(3),
+            },
+        ];
+        for adjudication in adjudications {
+            for (holes, beans) in [(5, 4), (4, 3), (6, 4), (6, 5), (3, 3)] {
+                let mut game = Game::new(
+                    Board::new(holes, beans),
+                    TestPlayer::boxed("First", None),
+                    TestPlayer::boxed("Second", None),
+                );
+                game.set_observer(Box::new(SilentObserver));
+                game.set_adjudication(adjudication);
+                game.play();
+                let status = game.status();
+
+                assert!(game.undo());
+                assert!(!game.status().over);
+                assert!(game.redo());
+                assert_eq!(game.status(), status);
+                assert_eq!(
+                    game.submit_move(Move::new(1).unwrap()).map(|_| ()),
+                    Err(GameError::Over)
+                );
+            }
+        }
+
+        // hole 4 ends in South's pot, which gives it a majority before its extra turn.
+        let mut b = Board::new(4, 0);
+        b.set_beans(Side::South, 1, 1).unwrap();
+        b.set_beans(Side::South, 4, 1).unwrap();
+        b.set_beans(Side::South, 0, 13).unwrap();
+        for hole in 1..=4 {
+            b.set_beans(Side::North, hole, 3).unwrap();
+        }
+        let mut game = Game::new_driven(b, Side::South, "Ann", "Bob");
+        game.set_observer(Box::new(SilentObserver));
+        game.set_adjudication(adjudications[0]);
+        game.submit_move(Move::new(4).unwrap()).unwrap();
+        let status = game.status();
+        assert_eq!(status.termination, Some(Termination::Majority));
+        assert_eq!(status.side_to_move, Side::South);
+
+        assert!(game.undo());
+        assert!(game.redo());
+        assert_eq!(game.status(), status);
     }
 }
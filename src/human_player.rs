@@ -1,16 +1,36 @@
-use crate::board::Board;
+#![allow(dead_code)]
+
 use crate::board::Side;
+use crate::board::{Board, BoardError, Move};
 use crate::player::Player;
-use std::io::{self, Write};
+use std::cell::{Cell, RefCell};
+use std::io::{self, BufRead, Write};
 
 pub struct HumanPlayer {
     name: String,
+    // where the answers are read from, if not stdin (which two players at one terminal share).
+    input: Option<RefCell<Box<dyn BufRead>>>,
+    // the first answer of a turn is read when asking about resigning and draws; if it was a
+    // hole, it is kept here for choose_move.
+    first_answer: RefCell<Option<String>>,
+    offering_draw: Cell<bool>,
 }
 
 impl HumanPlayer {
     pub fn new_from_name(name: &str) -> Self {
         HumanPlayer {
             name: name.to_owned(),
+            input: None,
+            first_answer: RefCell::new(None),
+            offering_draw: Cell::new(false),
+        }
+    }
+
+    // a player whose answers are read from input instead of stdin.
+    pub fn new_with_input(name: &str, input: Box<dyn BufRead>) -> Self {
+        HumanPlayer {
+            input: Some(RefCell::new(input)),
+            ..HumanPlayer::new_from_name(name)
         }
     }
 }
@@ -24,45 +44,42 @@ impl Player for HumanPlayer {
         true
     }
 
-    fn choose_move(&self, b: &Board, s: Side) -> i32 {
-        // first check for a legal move, and if none is available, then return -1
+    fn choose_move(&self, b: &Board, s: Side) -> Option<Move> {
+        // first check for a legal move, and if none is available, then return None
 
-        let mut has_legal_move = false;
-        for hole in 1..=b.holes() {
-            if b.beans(s, hole) > 0 {
-                has_legal_move = true;
-                break;
-            }
-        }
-        if !has_legal_move {
-            return -1;
+        if b.is_game_over(s) {
+            return None;
         }
 
         loop {
-            print!("Select a hole, {}: ", self.name);
-            io::stdout().flush().expect("failed to flush stdout!");
-
-            let mut input_text = String::new();
-            io::stdin()
-                .read_line(&mut input_text)
-                .expect("failed to read from stdin");
-
-            let trimmed = input_text.trim();
-            match trimmed.parse::<u32>() {
-                Ok(i) => {
-                    let i = i as i32;
-                    if i < 1 || i > b.holes() {
-                        println!("The hole number must be from 1 to {}.", b.holes());
-                        continue;
-                    } else {
-                        // check that there are beans in the hole:
-                        if b.beans(s, i) > 0 {
-                            return i;
-                        } else {
-                            println!("There are no beans in that hole.")
-                        }
+            let first_answer = self.first_answer.borrow_mut().take();
+            let answer = match first_answer {
+                Some(answer) => answer,
+                // with no more input there is no move to make.
+                None => self.read_answer(&format!("Select a hole, {}: ", self.name))?,
+            };
+
+            if answer == "resign" || answer == "draw" {
+                println!("You can only resign or offer a draw before choosing a hole.");
+                continue;
+            }
+            match answer.parse::<Move>() {
+                // check that the hole is on the board, that there are beans in it, and that the
+                // rules allow it:
+                Ok(mv) => match b.check_sowing(s, mv) {
+                    Ok(mv) => return Some(mv),
+                    Err(BoardError::EmptyHole(_)) => println!("There are no beans in that hole."),
+                    Err(BoardError::MustFeed(_)) => {
+                        println!("That hole does not give your opponent any beans.")
                     }
-                }
+                    Err(BoardError::NoDirection(_)) => {
+                        println!("Add cw or ccw to the hole number to say which way to sow.")
+                    }
+                    Err(BoardError::WrongDirection(_)) => {
+                        println!("These rules do not let you sow that way.")
+                    }
+                    Err(_) => println!("The hole number must be from 1 to {}.", b.holes()),
+                },
                 Err(..) => {
                     println!("The hole number must be from 1 to {}.", b.holes());
                     continue;
@@ -70,4 +87,127 @@ impl Player for HumanPlayer {
             };
         }
     }
+
+    fn should_swap(&self, _b: &Board, _s: Side) -> bool {
+        self.ask(&format!("Do you want to swap sides, {}?", self.name))
+    }
+
+    fn should_resign(&self, _b: &Board, _s: Side) -> bool {
+        // the turn starts with the hole prompt, which also takes resign or draw.
+        self.offering_draw.set(false);
+        let prompt = format!(
+            "Select a hole, {} (or type resign, or draw to offer one): ",
+            self.name
+        );
+        match self.read_answer(&prompt).as_deref() {
+            Some("resign") => true,
+            Some("draw") => {
+                self.offering_draw.set(true);
+                false
+            }
+            answer => {
+                *self.first_answer.borrow_mut() = answer.map(str::to_owned);
+                false
+            }
+        }
+    }
+
+    fn offers_draw(&self, _b: &Board, _s: Side) -> bool {
+        self.offering_draw.get()
+    }
+
+    fn accepts_draw(&self, _b: &Board, _s: Side) -> bool {
+        self.ask(&format!("Do you accept the draw, {}?", self.name))
+    }
+}
+
+impl HumanPlayer {
+    // prints prompt and reads a line of input, trimmed. None at the end of the input.
+    fn read_answer(&self, prompt: &str) -> Option<String> {
+        print!("{}", prompt);
+        io::stdout().flush().expect("failed to flush stdout!");
+
+        let mut input_text = String::new();
+        let read = match &self.input {
+            Some(input) => input.borrow_mut().read_line(&mut input_text),
+            None => io::stdin().read_line(&mut input_text),
+        };
+        match read.expect("failed to read input") {
+            0 => None,
+            _ => Some(input_text.trim().to_owned()),
+        }
+    }
+
+    // asks a yes or no question until it gets an answer (no, if the input runs out).
+    fn ask(&self, question: &str) -> bool {
+        loop {
+            let answer = match self.read_answer(&format!("{} (y/n): ", question)) {
+                Some(answer) => answer,
+                None => return false,
+            };
+
+            match answer.as_str() {
+                "y" | "Y" | "yes" => return true,
+                "n" | "N" | "no" => return false,
+                _ => println!("Please answer y or n."),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::game::Game;
+    use crate::observer::SilentObserver;
+    use crate::record::Termination;
+
+    fn typing(name: &str, lines: &str) -> Box<HumanPlayer> {
+        Box::new(HumanPlayer::new_with_input(
+            name,
+            Box::new(io::Cursor::new(lines.to_owned())),
+        ))
+    }
+
+    fn played(south: &str, north: &str) -> Game {
+        let mut game = Game::new(Board::new(4, 3), typing("Ann", south), typing("Bob", north));
+        game.set_observer(Box::new(SilentObserver));
+        game.play();
+        game
+    }
+
+    #[test]
+    fn resign_and_draw_are_typed_at_the_hole_prompt() {
+        // the first answer of a turn may be a hole; later ones must be.
+        let game = played("1\nresign\n", "x\ndraw\n1\n");
+        assert_eq!(game.record().moves.len(), 2);
+        assert_eq!(
+            game.status().termination,
+            Some(Termination::Resignation(Side::South))
+        );
+
+        // North is asked about the offer.
+        let game = played("draw\n", "maybe\ny\n");
+        assert!(game.record().moves.is_empty());
+        assert_eq!(game.status().termination, Some(Termination::DrawAgreed));
+
+        // a declined offer leaves the turn to be played.
+        let game = played("draw\n1\nresign\n", "n\n1\n");
+        assert_eq!(game.record().moves.len(), 2);
+        assert_eq!(
+            game.status().termination,
+            Some(Termination::Resignation(Side::South))
+        );
+    }
+
+    #[test]
+    fn running_out_of_input_chooses_no_move() {
+        let player = HumanPlayer::new_with_input("Ann", Box::new(io::empty()));
+        let b = Board::new(4, 3);
+        assert!(!player.should_resign(&b, Side::South));
+        assert!(!player.offers_draw(&b, Side::South));
+        assert_eq!(player.choose_move(&b, Side::South), None);
+        assert!(!player.accepts_draw(&b, Side::South));
+    }
 }
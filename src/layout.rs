@@ -0,0 +1,191 @@
+#![allow(dead_code)]
+
+// Starting positions other than the equal holes of Board::new: a layout given hole by hole, or
+// one dealt at random from a seeded Rng, so that a series of games can start from varied
+// positions that cannot be learnt by heart. Either way both sides start with the same number of
+// beans and empty pots.
+
+use crate::board::{Board, BoardError};
+use crate::rng::Rng;
+use crate::rules::Rules;
+
+// how Board::random_start deals out the beans.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Deal {
+    // South's beans go one at a time into holes picked at random, and North's holes are the
+    // mirror image of South's, so the board is its own mirror (see Board::mirrored) and neither
+    // side starts better off.
+    Mirrored,
+    // each side's beans are dealt separately, one at a time, into holes picked in proportion to
+    // the weights. The k-th weight is for South's hole k and for the hole in the same place on
+    // North's side, North's hole n + 1 - k, so the sides are dealt alike but not identically.
+    // There must be one weight per hole, none negative and not all 0, adding up to an i32.
+    Weighted(Vec<i32>),
+}
+
+impl Board {
+    // a board with South's holes holding layout (hole 1 first) and North's holes the mirror
+    // image, so that both sides start from the same position.
+    pub fn from_layout(layout: &[i32], rules: Rules) -> Result<Board, BoardError> {
+        let north: Vec<i32> = layout.iter().rev().copied().collect();
+        Board::from_contents(&north, layout, 0, 0, rules)
+    }
+
+    // a board with num_holes holes a side and the same beans in all as
+    // Board::new(num_holes, beans_per_hole), dealt out at random as deal says. The same rng
+    // state always deals the same board.
+    pub fn random_start(
+        num_holes: i32,
+        beans_per_hole: i32,
+        deal: &Deal,
+        rules: Rules,
+        rng: &mut Rng,
+    ) -> Result<Board, BoardError> {
+        if num_holes <= 0 {
+            return Err(BoardError::BadHoleCount(num_holes));
+        }
+        if beans_per_hole < 0 {
+            return Err(BoardError::NegativeBeans(beans_per_hole));
+        }
+        let beans = num_holes
+            .checked_mul(beans_per_hole)
+            .ok_or(BoardError::TooLarge)?;
+
+        match deal {
+            Deal::Mirrored => {
+                let south = deal_beans(&vec![1; num_holes as usize], beans, rng);
+                Board::from_layout(&south, rules)
+            }
+            Deal::Weighted(weights) => {
+                if weights.len() != num_holes as usize
+                    || weights.iter().any(|&w| w < 0)
+                    || weights.iter().all(|&w| w == 0)
+                    || weights
+                        .iter()
+                        .try_fold(0, |sum: i32, &w| sum.checked_add(w))
+                        .is_none()
+                {
+                    return Err(BoardError::BadWeights);
+                }
+                let south = deal_beans(weights, beans, rng);
+                let north: Vec<i32> = deal_beans(weights, beans, rng).into_iter().rev().collect();
+                Board::from_contents(&north, &south, 0, 0, rules)
+            }
+        }
+    }
+}
+
+// puts `beans` beans one at a time into holes picked at random in proportion to the weights,
+// and returns how many each hole got.
+fn deal_beans(weights: &[i32], beans: i32, rng: &mut Rng) -> Vec<i32> {
+    let total: i32 = weights.iter().sum();
+    let mut holes = vec![0; weights.len()];
+    for _ in 0..beans {
+        let mut pick = rng.range(0, total - 1);
+        let hole = weights
+            .iter()
+            .position(|&w| {
+                if pick < w {
+                    true
+                } else {
+                    pick -= w;
+                    false
+                }
+            })
+            .unwrap();
+        holes[hole] += 1;
+    }
+    holes
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::board::Side::*;
+
+    #[test]
+    fn layouts_are_the_same_for_both_sides() {
+        let b = Board::from_layout(&[1, 2, 3], Rules::default()).unwrap();
+        assert_eq!(b.to_string(), "3/1,2,3/0/3,2,1/0");
+        assert_eq!(b.mirrored(), b);
+        assert_eq!(b.total_beans(), 12);
+
+        assert_eq!(
+            Board::from_layout(&[], Rules::default()),
+            Err(BoardError::BadHoleCount(0))
+        );
+        assert_eq!(
+            Board::from_layout(&[1, -1], Rules::default()),
+            Err(BoardError::NegativeBeans(-1))
+        );
+    }
+
+    #[test]
+    fn mirrored_deals_are_fair_and_reproducible() {
+        let deal = |seed| {
+            Board::random_start(6, 4, &Deal::Mirrored, Rules::default(), &mut Rng::new(seed))
+                .unwrap()
+        };
+        for seed in 0..20 {
+            let b = deal(seed);
+            assert_eq!(b, deal(seed));
+            assert_eq!(b.mirrored(), b);
+            assert_eq!(b.beans_in_play(South), 24);
+            assert_eq!(b.beans_in_play(North), 24);
+        }
+        assert_ne!(deal(1), deal(2));
+        assert_ne!(deal(1), Board::new(6, 4));
+    }
+
+    #[test]
+    fn weighted_deals_follow_the_weights() {
+        let weights = Deal::Weighted(vec![0, 1, 3]);
+        let mut rng = Rng::new(8);
+        let b = Board::random_start(3, 10, &weights, Rules::oware(), &mut rng).unwrap();
+        assert_eq!(b.rules(), Rules::oware());
+        assert_eq!(b.beans_in_play(South), 30);
+        assert_eq!(b.beans_in_play(North), 30);
+        // the hole with no weight is empty on both sides, and the heaviest holds the most.
+        assert_eq!((b.beans(South, 1), b.beans(North, 3)), (0, 0));
+        assert!(b.beans(South, 3) > b.beans(South, 2));
+        assert!(b.beans(North, 1) > b.beans(North, 2));
+    }
+
+    #[test]
+    fn bad_deals() {
+        let mut rng = Rng::new(1);
+        let mut start = |holes, beans, deal: Deal| {
+            Board::random_start(holes, beans, &deal, Rules::default(), &mut rng)
+        };
+        assert_eq!(
+            start(0, 4, Deal::Mirrored),
+            Err(BoardError::BadHoleCount(0))
+        );
+        assert_eq!(
+            start(6, -4, Deal::Mirrored),
+            Err(BoardError::NegativeBeans(-4))
+        );
+        assert_eq!(
+            start(2, 4, Deal::Weighted(vec![1, 1, 1])),
+            Err(BoardError::BadWeights)
+        );
+        assert_eq!(
+            start(2, 4, Deal::Weighted(vec![0, 0])),
+            Err(BoardError::BadWeights)
+        );
+        assert_eq!(
+            start(2, 4, Deal::Weighted(vec![2, -1])),
+            Err(BoardError::BadWeights)
+        );
+        assert_eq!(
+            start(2, 4, Deal::Weighted(vec![i32::MAX, 1])),
+            Err(BoardError::BadWeights)
+        );
+        assert!(start(2, 4, Deal::Weighted(vec![i32::MAX - 1, 1])).is_ok());
+        assert_eq!(
+            start(2, 0, Deal::Weighted(vec![1, 0])),
+            Ok(Board::new(2, 0))
+        );
+    }
+}
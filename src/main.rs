@@ -1,23 +1,62 @@
-use bad_player::BadPlayer;
-use board::Board;
-use game::Game;
+use board::{Board, Side};
+use game::{Adjudication, Game};
 use human_player::HumanPlayer;
+use notation::Position;
 use smart_player::SmartPlayer;
 
 mod bad_player;
 mod board;
+mod clock;
 mod game;
 mod human_player;
+mod layout;
+mod notation;
+mod observer;
+mod perft;
+mod pits;
 mod player;
+mod record;
+mod rng;
+mod rules;
 mod smart_player;
+mod transposition;
+mod zobrist;
 
 fn main() {
+    // `kalah bench [depth]` measures the search speed instead of playing.
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("bench") {
+        let depth = args.get(2).and_then(|d| d.parse().ok()).unwrap_or(12);
+        smart_player::benchmark(depth);
+        return;
+    }
+    // `kalah perft depth [position]` counts the move sequences from a position (the Kalah(6,4)
+    // opening by default), broken down by first move.
+    if args.get(1).map(String::as_str) == Some("perft") {
+        let depth = args.get(2).and_then(|d| d.parse().ok()).unwrap_or(4);
+        let position = match args.get(3).map(|p| p.parse::<Position>()) {
+            None => Position::new(Board::new(6, 4), Side::South),
+            Some(Ok(position)) => position,
+            Some(Err(err)) => {
+                println!("bad position: {}", err);
+                return;
+            }
+        };
+        perft::print_divide(position, depth);
+        return;
+    }
+
     let player1 = SmartPlayer::new_from_name("Homer");
     let player2 = HumanPlayer::new_from_name("Eric");
 
     let board = Board::new(6, 4);
 
     let mut game = Game::new(board, Box::new(player2), Box::new(player1));
+    // no need to play on once one side has more than half of the beans.
+    game.set_adjudication(Adjudication {
+        majority: true,
+        move_limit: None,
+    });
 
     game.play();
 }
@@ -0,0 +1,346 @@
+#![allow(dead_code)]
+
+// A one-line text notation for positions, for bug reports, test fixtures and analysis tools.
+// Fields are separated by slashes:
+//
+//     holes/south holes/south pot/north holes/north pot/side to move
+//
+// with each side's holes listed from hole 1 to hole n, separated by commas, and the side to move
+// written as S or N. The standard Kalah(6,4) opening with South to move is
+//
+//     6/4,4,4,4,4,4/0/4,4,4,4,4,4/0/S
+//
+// A Board on its own prints (and parses) as the first five fields; a Position adds the side to
+// move. A board under rules other than the default ones is followed by the rules, written as in
+// a game record, in square brackets:
+//
+//     6/4,4,4,4,4,4/0/4,4,4,4,4,4/0/S [oware]
+//
+// and a parsed board without them gets the default rules.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::{Board, Side};
+use crate::rules::Rules;
+
+// a board together with the side to move, which is what the notation describes.
+#[derive(Clone)]
+pub struct Position {
+    pub board: Board,
+    pub side_to_move: Side,
+}
+
+impl Position {
+    pub fn new(board: Board, side_to_move: Side) -> Self {
+        Position {
+            board,
+            side_to_move,
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ParseError {
+    // the notation has the wrong number of slash-separated fields (6 for a position, 5 for a
+    // board); holds the number found
+    WrongFieldCount(usize),
+    // a field that should be a number is not one
+    NotANumber(String),
+    // the hole count is zero or negative
+    BadHoleCount(i32),
+    // a side lists a different number of holes than the hole count says
+    WrongHoleCount {
+        side: Side,
+        expected: i32,
+        found: usize,
+    },
+    // a hole or pot holds a negative number of beans
+    NegativeCount(i32),
+    // the side to move is not S or N
+    BadSide(String),
+    // the rules in brackets cannot be read; holds why
+    BadRules(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFieldCount(n) => {
+                write!(f, "wrong number of fields separated by '/': found {}", n)
+            }
+            ParseError::NotANumber(text) => write!(f, "'{}' is not a number", text),
+            ParseError::BadHoleCount(n) => {
+                write!(f, "the number of holes must be positive, not {}", n)
+            }
+            ParseError::WrongHoleCount {
+                side,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{:?} has {} holes listed, but the board has {}",
+                side, found, expected
+            ),
+            ParseError::NegativeCount(n) => {
+                write!(f, "a hole or pot cannot hold {} beans", n)
+            }
+            ParseError::BadSide(text) => {
+                write!(f, "the side to move must be S or N, not '{}'", text)
+            }
+            ParseError::BadRules(reason) => write!(f, "bad rules: {}", reason),
+        }
+    }
+}
+
+pub fn side_letter(s: Side) -> char {
+    match s {
+        Side::North => 'N',
+        Side::South => 'S',
+    }
+}
+
+pub fn parse_side_letter(text: &str) -> Option<Side> {
+    match text.trim() {
+        "S" => Some(Side::South),
+        "N" => Some(Side::North),
+        _ => None,
+    }
+}
+
+fn parse_count(text: &str) -> Result<i32, ParseError> {
+    let n = text
+        .trim()
+        .parse::<i32>()
+        .map_err(|_| ParseError::NotANumber(text.trim().to_owned()))?;
+    if n < 0 {
+        return Err(ParseError::NegativeCount(n));
+    }
+    Ok(n)
+}
+
+// splits the rules in brackets, if any, off the end of text, and reads them.
+fn split_rules(text: &str) -> Result<(&str, Rules), ParseError> {
+    let text = text.trim();
+    let Some(rest) = text.strip_suffix(']') else {
+        return Ok((text, Rules::default()));
+    };
+    let (fields, rules) = rest
+        .rsplit_once('[')
+        .ok_or_else(|| ParseError::BadRules("no '[' before the ']'".to_owned()))?;
+    let rules = rules.parse().map_err(ParseError::BadRules)?;
+    Ok((fields.trim(), rules))
+}
+
+// writes the rules in brackets after the rest of the notation, unless they are the default ones.
+fn write_rules(f: &mut fmt::Formatter, rules: Rules) -> fmt::Result {
+    if rules != Rules::default() {
+        write!(f, " [{}]", rules)?;
+    }
+    Ok(())
+}
+
+fn parse_holes(text: &str, side: Side, expected: i32) -> Result<Vec<i32>, ParseError> {
+    let holes = text
+        .split(',')
+        .map(parse_count)
+        .collect::<Result<Vec<i32>, ParseError>>()?;
+    if holes.len() != expected as usize {
+        return Err(ParseError::WrongHoleCount {
+            side,
+            expected,
+            found: holes.len(),
+        });
+    }
+    Ok(holes)
+}
+
+// writes the five fields of the notation that describe the board itself.
+fn write_fields(f: &mut fmt::Formatter, board: &Board) -> fmt::Result {
+    let holes = |s: Side| {
+        (1..=board.holes())
+            .map(|hole| board.beans(s, hole).to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+    write!(
+        f,
+        "{}/{}/{}/{}/{}",
+        board.holes(),
+        holes(Side::South),
+        board.beans(Side::South, 0),
+        holes(Side::North),
+        board.beans(Side::North, 0)
+    )
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_fields(f, self)?;
+        write_rules(f, self.rules())
+    }
+}
+
+impl FromStr for Board {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (text, rules) = split_rules(text)?;
+        let fields: Vec<&str> = text.split('/').collect();
+        if fields.len() != 5 {
+            return Err(ParseError::WrongFieldCount(fields.len()));
+        }
+
+        let num_holes = fields[0]
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| ParseError::NotANumber(fields[0].trim().to_owned()))?;
+        if num_holes <= 0 {
+            return Err(ParseError::BadHoleCount(num_holes));
+        }
+
+        let south = parse_holes(fields[1], Side::South, num_holes)?;
+        let south_pot = parse_count(fields[2])?;
+        let north = parse_holes(fields[3], Side::North, num_holes)?;
+        let north_pot = parse_count(fields[4])?;
+
+        // both sides have num_holes holes and no count is negative, so this cannot fail.
+        let board = Board::from_contents(&north, &south, north_pot, south_pot, rules)
+            .expect("the notation has been checked");
+        Ok(board)
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_fields(f, &self.board)?;
+        write!(f, "/{}", side_letter(self.side_to_move))?;
+        write_rules(f, self.board.rules())
+    }
+}
+
+impl FromStr for Position {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (text, rules) = split_rules(text)?;
+        let num_fields = text.split('/').count();
+        if num_fields != 6 {
+            return Err(ParseError::WrongFieldCount(num_fields));
+        }
+
+        let (board, side) = text.rsplit_once('/').unwrap();
+        let side_to_move =
+            parse_side_letter(side).ok_or_else(|| ParseError::BadSide(side.trim().to_owned()))?;
+
+        let mut board: Board = board.parse()?;
+        board.set_rules(rules);
+        Ok(Position {
+            board,
+            side_to_move,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn print_opening() {
+        let pos = Position::new(Board::new(6, 4), Side::South);
+        assert_eq!(pos.to_string(), "6/4,4,4,4,4,4/0/4,4,4,4,4,4/0/S");
+    }
+
+    #[test]
+    fn parse_and_print_round_trip() {
+        let text = "3/0,1,12/7/2,0,5/3/N";
+        let pos: Position = text.parse().unwrap();
+        assert_eq!(pos.side_to_move, Side::North);
+        assert_eq!(pos.board.holes(), 3);
+        assert_eq!(pos.board.beans(Side::South, 3), 12);
+        assert_eq!(pos.board.beans(Side::South, 0), 7);
+        assert_eq!(pos.board.beans(Side::North, 1), 2);
+        assert_eq!(pos.board.beans(Side::North, 0), 3);
+        assert_eq!(pos.board.total_beans(), 30);
+        assert_eq!(pos.board.hash(), pos.board.compute_hash());
+        assert_eq!(pos.to_string(), text);
+    }
+
+    #[test]
+    fn boards_without_side_to_move() {
+        let b: Board = "2/1,2/0/3,4/5".parse().unwrap();
+        assert_eq!(b.beans(Side::North, 0), 5);
+        assert_eq!(b.to_string(), "2/1,2/0/3,4/5");
+        assert_eq!(
+            "2/1,2/0/3,4/5/S".parse::<Board>().err(),
+            Some(ParseError::WrongFieldCount(6))
+        );
+    }
+
+    #[test]
+    fn whitespace_around_fields_is_allowed() {
+        let pos: Position = " 2/ 1, 2 /0/3,4/ 0 /S\n".parse().unwrap();
+        assert_eq!(pos.to_string(), "2/1,2/0/3,4/0/S");
+    }
+
+    #[test]
+    fn rules_other_than_the_default_are_kept() {
+        use crate::rules::{Direction, SowingRule, StoreRule};
+
+        let oware = Position::new(Board::new_with_rules(2, 1, Rules::oware()), Side::North);
+        assert_eq!(oware.to_string(), "2/1,1/0/1,1/0/N [oware]");
+        let parsed: Position = oware.to_string().parse().unwrap();
+        assert_eq!(parsed.board, oware.board);
+        assert_eq!(parsed.board.rules(), Rules::oware());
+
+        let rules = Rules::kalah()
+            .with_sowing(SowingRule::Always(Direction::Clockwise))
+            .with_stores(StoreRule::Both);
+        let b = Board::new_with_rules(3, 2, rules);
+        assert_eq!(
+            b.to_string(),
+            "3/2,2,2/0/2,2,2/0 [kalah sowing=clockwise stores=both]"
+        );
+        assert_eq!(b.to_string().parse::<Board>().unwrap().rules(), rules);
+
+        // the default rules are left out.
+        assert_eq!(Board::new(2, 1).to_string(), "2/1,1/0/1,1/0");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = |text: &str| text.parse::<Position>().err().unwrap();
+        assert_eq!(err("2/1,2/0/3,4/0"), ParseError::WrongFieldCount(5));
+        assert_eq!(err("2/1,2/0/3,4/0/S/x"), ParseError::WrongFieldCount(7));
+        assert_eq!(err("0//0//0/S"), ParseError::BadHoleCount(0));
+        assert_eq!(
+            err("two/1,2/0/3,4/0/S"),
+            ParseError::NotANumber("two".to_owned())
+        );
+        assert_eq!(
+            err("2/1,x/0/3,4/0/S"),
+            ParseError::NotANumber("x".to_owned())
+        );
+        assert_eq!(err("2/1,-2/0/3,4/0/S"), ParseError::NegativeCount(-2));
+        assert_eq!(err("2/1,2/0/3,4/-1/S"), ParseError::NegativeCount(-1));
+        assert_eq!(
+            err("2/1,2/0/3,4,5/0/S"),
+            ParseError::WrongHoleCount {
+                side: Side::North,
+                expected: 2,
+                found: 3
+            }
+        );
+        assert_eq!(err("2/1,2/0/3,4/0/W"), ParseError::BadSide("W".to_owned()));
+        assert!(matches!(
+            err("2/1,2/0/3,4/0/S [chess]"),
+            ParseError::BadRules(_)
+        ));
+        assert!(matches!(
+            err("2/1,2/0/3,4/0/S oware]"),
+            ParseError::BadRules(_)
+        ));
+    }
+}
@@ -0,0 +1,255 @@
+#![allow(dead_code)]
+
+// What happens in a Game, told to whatever shows the game to people: the terminal, a GUI, a
+// server, or nothing at all in a batch run. Game never prints; it hands every event to its
+// observer along with a view of the game at that moment. TextRenderer prints what the game
+// always printed, and SilentObserver ignores everything.
+
+use crate::board::{Board, BoardError, Move, Side, Sweep};
+use crate::clock::{clock_text, Clock};
+use crate::record::Termination;
+
+// one thing that happened in a game.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GameEvent {
+    // side is about to make a complete move.
+    TurnStarted {
+        side: Side,
+    },
+    // the player on side picked mv, with what it had to say about why (see
+    // Player::explain_choice).
+    MoveChosen {
+        side: Side,
+        mv: Move,
+        explanation: Option<String>,
+    },
+    // the move the player on side picked cannot be played. What happens next is up to the
+    // game's IllegalMovePolicy.
+    MoveRejected {
+        side: Side,
+        mv: Move,
+        error: BoardError,
+    },
+    // the player on side did not pick a move at all, which counts as an illegal move.
+    NoMoveChosen {
+        side: Side,
+    },
+    // side's player failed to pick a legal move, so the game plays mv, picked at random, in
+    // its place.
+    MoveSubstituted {
+        side: Side,
+        mv: Move,
+    },
+    // side's sowing captured beans into its pot.
+    Captured {
+        side: Side,
+        beans: i32,
+    },
+    // side's sowing ended in its pot, so it sows again.
+    ExtraTurn {
+        side: Side,
+    },
+    // under the pie rule, the player replying to the first move was offered side, the side
+    // that made it, and took it over (swapped) or declined.
+    SwapOffered {
+        side: Side,
+        swapped: bool,
+    },
+    // the player on side offered a draw.
+    DrawOffered {
+        side: Side,
+    },
+    // the player on side turned down the draw its opponent offered.
+    DrawDeclined {
+        side: Side,
+    },
+    // the game ended with the beans left in play being swept into a pot.
+    Swept(Sweep),
+    // the game is over; winner is None for a tie.
+    GameOver {
+        winner: Option<Side>,
+        sides_swapped: bool,
+        termination: Termination,
+    },
+}
+
+// the game as an observer sees it when an event happens: the board as it is after the event,
+// the names of the players now on each side, and the clocks in a timed game.
+pub struct GameView<'a> {
+    pub board: &'a Board,
+    pub south: &'a str,
+    pub north: &'a str,
+    pub clock: Option<&'a Clock>,
+}
+
+impl GameView<'_> {
+    pub fn name(&self, s: Side) -> &str {
+        match s {
+            Side::North => self.north,
+            Side::South => self.south,
+        }
+    }
+
+    // the player's name, followed by the time on its clock in a timed game.
+    pub fn label(&self, s: Side) -> String {
+        match self.clock {
+            Some(clock) => format!("{} ({})", self.name(s), clock_text(clock.remaining(s))),
+            None => self.name(s).to_owned(),
+        }
+    }
+}
+
+pub trait GameObserver {
+    fn notify(&mut self, view: &GameView, event: &GameEvent);
+}
+
+// for games nobody is watching.
+pub struct SilentObserver;
+
+impl GameObserver for SilentObserver {
+    fn notify(&mut self, _view: &GameView, _event: &GameEvent) {}
+}
+
+// prints the game to stdout as it goes, drawing the board at the start of every turn.
+pub struct TextRenderer;
+
+impl TextRenderer {
+    pub fn display(view: &GameView) {
+        let board = view.board;
+        let total_line_length = 2 * board.holes() + 5;
+
+        // print north name
+        let north = view.label(Side::North);
+        let shift_amt = (total_line_length - north.len() as i32) / 2;
+        for _ in 0..shift_amt {
+            print!(" ");
+        }
+        println!("{}", north);
+
+        // print north holes
+        print!("   ");
+        for hole in 1..=board.holes() {
+            print!("{}", board.beans(Side::North, hole));
+            print!(" ");
+        }
+        println!();
+
+        // print both players' pots
+        print!(" ");
+        print!("{} ", board.beans(Side::North, 0));
+        for _ in 1..=board.holes() {
+            print!("  ")
+        }
+        println!("{} ", board.beans(Side::South, 0));
+
+        // print south holes
+        print!("   ");
+        for hole in 1..=board.holes() {
+            print!("{}", board.beans(Side::South, hole));
+            print!(" ");
+        }
+        println!();
+
+        // print south name
+        let south = view.label(Side::South);
+        let shift_amt = (total_line_length - south.len() as i32) / 2;
+        for _ in 0..shift_amt {
+            print!(" ");
+        }
+        println!("{}", south);
+    }
+}
+
+impl GameObserver for TextRenderer {
+    fn notify(&mut self, view: &GameView, event: &GameEvent) {
+        match event {
+            GameEvent::TurnStarted { .. } => TextRenderer::display(view),
+            GameEvent::MoveChosen {
+                side,
+                mv,
+                explanation,
+            } => {
+                if let Some(explanation) = explanation {
+                    println!("{} {}", view.name(*side), explanation);
+                }
+                println!("{} chooses hole {}", view.name(*side), mv);
+            }
+            GameEvent::MoveRejected { side, error, .. } => {
+                println!("{} cannot play that: {}.", view.name(*side), error)
+            }
+            GameEvent::NoMoveChosen { side } => {
+                println!("{} did not choose a move.", view.name(*side))
+            }
+            GameEvent::MoveSubstituted { side, mv } => {
+                println!("Hole {} is played for {} instead.", mv, view.name(*side))
+            }
+            GameEvent::Captured { side, beans } => {
+                println!("{} captures {} beans.", view.name(*side), beans)
+            }
+            GameEvent::ExtraTurn { side } => {
+                TextRenderer::display(view);
+                println!("{} gets another turn", view.name(*side));
+            }
+            GameEvent::SwapOffered {
+                side,
+                swapped: true,
+            } => println!(
+                "{} swaps sides and takes over {:?}.",
+                view.name(*side),
+                side
+            ),
+            GameEvent::SwapOffered {
+                side,
+                swapped: false,
+            } => println!("{} declines to swap sides.", view.name(side.opponent())),
+            GameEvent::DrawOffered { side } => println!("{} offers a draw.", view.name(*side)),
+            GameEvent::DrawDeclined { side } => {
+                println!("{} declines the draw.", view.name(*side))
+            }
+            GameEvent::Swept(sweep) => {
+                println!("{} has no beans left to sow.", view.name(sweep.empty_side));
+                println!(
+                    "Sweeping remaining beans into {}'s pot.",
+                    view.name(sweep.pot_owner)
+                );
+            }
+            GameEvent::GameOver {
+                winner,
+                sides_swapped,
+                termination,
+            } => {
+                TextRenderer::display(view);
+                match termination {
+                    Termination::IllegalMove(side) => println!(
+                        "{} forfeits the game for choosing moves it cannot play.",
+                        view.name(*side)
+                    ),
+                    Termination::TimeForfeit(side) => {
+                        println!("{} has run out of time.", view.name(*side))
+                    }
+                    Termination::Resignation(side) => println!("{} resigns.", view.name(*side)),
+                    Termination::DrawAgreed => println!("The players agree to a draw."),
+                    Termination::Majority => {
+                        println!(
+                            "One pot holds more than half of the beans, which decides the game."
+                        )
+                    }
+                    Termination::MoveLimit => {
+                        println!("The game has reached its move limit and is decided by the pots.")
+                    }
+                    Termination::Normal => {}
+                }
+                match winner {
+                    Some(side) => println!("The winner is {}.", view.name(*side)),
+                    None => println!("The game is a tie."),
+                }
+                if *sides_swapped {
+                    println!(
+                        "({} and {} swapped sides under the pie rule.)",
+                        view.south, view.north
+                    );
+                }
+            }
+        }
+    }
+}
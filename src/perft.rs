@@ -0,0 +1,201 @@
+#![allow(dead_code)]
+
+// Move-path counting ("perft", as chess programmers call it) for checking the rules: the number
+// of different sequences of complete moves of a given length from a position, where a complete
+// move is everything one side does in a turn, so a chain of sowings earning extra turns counts
+// as one move. As in chess, only sequences of exactly that length are counted: a line on which
+// the game ends early adds nothing. Any change to sowing, captures or the sweep that alters the
+// game tree changes these counts, so the known counts in the tests below guard Board and the
+// move logic the game and the search share.
+
+use crate::board::{Board, Move, Side};
+use crate::notation::Position;
+use crate::pits::Pits;
+
+// the number of sequences of `depth` complete moves from b with side_to_move to move. b is left
+// as it was found.
+pub fn perft<P: Pits>(b: &mut Board<P>, side_to_move: Side, depth: i32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut nodes = 0;
+    for_each_complete_move(
+        b,
+        side_to_move,
+        &mut Vec::new(),
+        &mut |b, next_to_move, _| {
+            nodes += perft(b, next_to_move, depth - 1);
+        },
+    );
+    nodes
+}
+
+// perft broken down by the first complete move: each move side_to_move can make (the holes it
+// sows in order) with the count of sequences starting with it, in the order legal_moves gives
+// them. The counts add up to perft(b, side_to_move, depth).
+pub fn divide<P: Pits>(b: &mut Board<P>, side_to_move: Side, depth: i32) -> Vec<(Vec<Move>, u64)> {
+    let mut counts = Vec::new();
+    if depth == 0 {
+        return counts;
+    }
+    for_each_complete_move(
+        b,
+        side_to_move,
+        &mut Vec::new(),
+        &mut |b, next_to_move, sowings| {
+            counts.push((sowings.to_vec(), perft(b, next_to_move, depth - 1)));
+        },
+    );
+    counts
+}
+
+// prints divide for the position, one first move (its holes in order) per line, and then the
+// total.
+pub fn print_divide(position: Position, depth: i32) {
+    let Position {
+        mut board,
+        side_to_move,
+    } = position;
+    let counts = divide(&mut board, side_to_move, depth);
+    for (sowings, count) in &counts {
+        let holes: Vec<String> = sowings.iter().map(|mv| mv.to_string()).collect();
+        println!("{}: {}", holes.join(" "), count);
+    }
+    let total: u64 = counts.iter().map(|&(_, count)| count).sum();
+    println!("total: {}", total);
+}
+
+// calls visit with the board after each complete move side can make, the side to move after it
+// and the sowings that made it up (after those already in `sowings`), taking the move back
+// afterwards.
+fn for_each_complete_move<P: Pits, F: FnMut(&mut Board<P>, Side, &[Move])>(
+    b: &mut Board<P>,
+    side: Side,
+    sowings: &mut Vec<Move>,
+    visit: &mut F,
+) {
+    let moves: Vec<Move> = b.legal_moves(side).collect();
+    for mv in moves {
+        let undo = b.make_move(side, mv).unwrap();
+        sowings.push(mv);
+        let outcome = undo.outcome;
+        if outcome.extra_turn && !outcome.game_over() {
+            for_each_complete_move(b, side, sowings, visit);
+        } else {
+            visit(b, outcome.next_to_move, sowings);
+        }
+        sowings.pop();
+        b.unmake_move(&undo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::board::CompactBoard;
+    use crate::rules::{Direction, Rules, SowingRule, StoreRule};
+
+    #[test]
+    fn first_moves_of_kalah() {
+        // five single sowings, plus hole 3 (which ends in the pot) followed by any of the five
+        // holes still holding beans.
+        let mut b = Board::new(6, 4);
+        assert_eq!(perft(&mut b, Side::South, 1), 10);
+        let chains = divide(&mut b, Side::South, 1)
+            .into_iter()
+            .filter(|(sowings, _)| sowings.len() == 2)
+            .count();
+        assert_eq!(chains, 5);
+    }
+
+    #[test]
+    fn divide_adds_up_to_perft() {
+        let mut b = Board::new(4, 3);
+        for depth in 1..=4 {
+            let counts = divide(&mut b, Side::North, depth);
+            let total: u64 = counts.iter().map(|&(_, count)| count).sum();
+            assert_eq!(total, perft(&mut b, Side::North, depth));
+        }
+        assert!(divide(&mut b, Side::North, 0).is_empty());
+        assert_eq!(perft(&mut b, Side::North, 0), 1);
+    }
+
+    #[test]
+    fn finished_games_have_no_moves() {
+        let mut b = Board::new(3, 0);
+        assert_eq!(perft(&mut b, Side::South, 0), 1);
+        assert_eq!(perft(&mut b, Side::South, 1), 0);
+    }
+
+    // the known counts: for each starting position, the counts from depth 1 up.
+    fn known_counts() -> Vec<(&'static str, Board, Vec<u64>)> {
+        let kalah = |holes, beans, rules| Board::new_with_rules(holes, beans, rules);
+        vec![
+            (
+                "kalah(6,4)",
+                Board::new(6, 4),
+                vec![10, 116, 1022, 9682, 125843],
+            ),
+            (
+                "kalah(4,3)",
+                Board::new(4, 3),
+                vec![6, 40, 229, 911, 3916, 15061],
+            ),
+            (
+                "oware(6,4)",
+                kalah(6, 4, Rules::oware()),
+                vec![6, 36, 190, 1014, 5219, 27332],
+            ),
+            (
+                "kalah(6,4) clockwise",
+                kalah(
+                    6,
+                    4,
+                    Rules::kalah().with_sowing(SowingRule::Always(Direction::Clockwise)),
+                ),
+                vec![6, 36, 190, 1007, 5229, 28659],
+            ),
+            (
+                "kalah(4,3) either way, both stores",
+                kalah(
+                    4,
+                    3,
+                    Rules::kalah()
+                        .with_sowing(SowingRule::PlayersChoice)
+                        .with_stores(StoreRule::Both),
+                ),
+                vec![13, 179, 1948, 19645],
+            ),
+        ]
+    }
+
+    #[test]
+    fn known_perft_counts() {
+        for (name, board, counts) in known_counts() {
+            for (depth, &expected) in (1..).zip(counts.iter()) {
+                let mut b = board.clone();
+                assert_eq!(
+                    perft(&mut b, Side::South, depth),
+                    expected,
+                    "{} at depth {}",
+                    name,
+                    depth
+                );
+                assert_eq!(b, board);
+            }
+        }
+    }
+
+    #[test]
+    fn compact_boards_count_the_same() {
+        for (_, board, counts) in known_counts() {
+            let mut compact = CompactBoard::try_from(&board).unwrap();
+            let depth = counts.len().min(3) as i32;
+            assert_eq!(
+                perft(&mut compact, Side::South, depth),
+                counts[depth as usize - 1]
+            );
+        }
+    }
+}
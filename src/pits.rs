@@ -0,0 +1,155 @@
+#![allow(dead_code)]
+
+// How a Board stores its bean counts. The rules only ever read and write single pits, so they
+// work the same on any storage: VecPits grows to any number of holes and beans, and InlinePits
+// keeps everything in a fixed-size array of small counts so that a board using it is Copy and
+// never allocates, which is what the search wants.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::board::Side;
+
+pub trait Pits: Clone + Eq + Hash + Debug {
+    // the most beans the storage can hold in one pit.
+    const MAX_BEANS: i32;
+
+    // a copy of the beans in one side's holes (not its pot), as holes returns it.
+    type Holes: Clone + Eq + Debug;
+
+    // empty storage for num_holes holes a side, or None if that is more than it can hold.
+    fn with_holes(num_holes: i32) -> Option<Self>;
+
+    // the beans in side s's hole (hole 0 being the pot). The hole must be on the board.
+    fn get(&self, s: Side, hole: i32) -> i32;
+
+    fn set(&mut self, s: Side, hole: i32, beans: i32);
+
+    // the beans in each of side s's holes.
+    fn holes(&self, s: Side) -> Self::Holes;
+
+    // the beans a copy from holes says were in the hole.
+    fn saved_beans(holes: &Self::Holes, hole: i32) -> i32;
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct VecPits {
+    north_holes: Vec<i32>,
+    south_holes: Vec<i32>,
+    north_pot: i32,
+    south_pot: i32,
+}
+
+impl Pits for VecPits {
+    const MAX_BEANS: i32 = i32::MAX;
+
+    type Holes = Vec<i32>;
+
+    fn with_holes(num_holes: i32) -> Option<Self> {
+        Some(VecPits {
+            north_holes: vec![0; num_holes as usize],
+            south_holes: vec![0; num_holes as usize],
+            north_pot: 0,
+            south_pot: 0,
+        })
+    }
+
+    fn get(&self, s: Side, hole: i32) -> i32 {
+        if hole == 0 {
+            match s {
+                Side::North => return self.north_pot,
+                Side::South => return self.south_pot,
+            }
+        }
+        match s {
+            Side::North => self.north_holes[(hole - 1) as usize],
+            Side::South => self.south_holes[(hole - 1) as usize],
+        }
+    }
+
+    fn set(&mut self, s: Side, hole: i32, beans: i32) {
+        if hole == 0 {
+            match s {
+                Side::North => {
+                    self.north_pot = beans;
+                }
+                Side::South => {
+                    self.south_pot = beans;
+                }
+            }
+        } else {
+            match s {
+                Side::North => {
+                    self.north_holes[(hole - 1) as usize] = beans;
+                }
+                Side::South => {
+                    self.south_holes[(hole - 1) as usize] = beans;
+                }
+            }
+        }
+    }
+
+    fn holes(&self, s: Side) -> Vec<i32> {
+        match s {
+            Side::North => self.north_holes.clone(),
+            Side::South => self.south_holes.clone(),
+        }
+    }
+
+    fn saved_beans(holes: &Vec<i32>, hole: i32) -> i32 {
+        holes[(hole - 1) as usize]
+    }
+}
+
+// the most holes a side can have in InlinePits.
+pub const MAX_INLINE_HOLES: i32 = 16;
+
+// both sides' pits in one array: North's pot and holes first, then South's.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct InlinePits {
+    pits: [u16; 2 * (MAX_INLINE_HOLES as usize + 1)],
+}
+
+impl InlinePits {
+    fn index(s: Side, hole: i32) -> usize {
+        match s {
+            Side::North => hole as usize,
+            Side::South => (MAX_INLINE_HOLES + 1 + hole) as usize,
+        }
+    }
+}
+
+impl Pits for InlinePits {
+    const MAX_BEANS: i32 = u16::MAX as i32;
+
+    type Holes = [u16; MAX_INLINE_HOLES as usize];
+
+    fn with_holes(num_holes: i32) -> Option<Self> {
+        if num_holes > MAX_INLINE_HOLES {
+            return None;
+        }
+        Some(InlinePits {
+            pits: [0; 2 * (MAX_INLINE_HOLES as usize + 1)],
+        })
+    }
+
+    fn get(&self, s: Side, hole: i32) -> i32 {
+        self.pits[InlinePits::index(s, hole)] as i32
+    }
+
+    fn set(&mut self, s: Side, hole: i32, beans: i32) {
+        debug_assert!((0..=InlinePits::MAX_BEANS).contains(&beans));
+        self.pits[InlinePits::index(s, hole)] = beans as u16;
+    }
+
+    fn holes(&self, s: Side) -> Self::Holes {
+        let first = InlinePits::index(s, 1);
+        self.pits[first..first + MAX_INLINE_HOLES as usize]
+            .try_into()
+            .unwrap()
+    }
+
+    fn saved_beans(holes: &Self::Holes, hole: i32) -> i32 {
+        holes[(hole - 1) as usize] as i32
+    }
+}
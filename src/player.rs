@@ -1,6 +1,46 @@
-use super::board::{Board, Side};
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use super::board::{Board, Move, Side};
+use super::clock::TimeControl;
 pub trait Player {
     fn get_name(&self) -> String;
     fn is_interactive(&self) -> bool;
-    fn choose_move(&self, b: &Board, s: Side) -> i32;
+    // the move the player makes for side s, or None if there is no legal move.
+    fn choose_move(&self, b: &Board, s: Side) -> Option<Move>;
+
+    // called under the pie rule once the first player has made their first complete move: the
+    // player on side s may take over the opponent's side (and that position) instead of replying.
+    fn should_swap(&self, _b: &Board, _s: Side) -> bool {
+        false
+    }
+
+    // asked at the start of each of the player's turns, before anything else: true gives up
+    // the game.
+    fn should_resign(&self, _b: &Board, _s: Side) -> bool {
+        false
+    }
+
+    // asked at the start of each of the player's turns, if it has not resigned: true offers the
+    // opponent a draw, which it may accept with accepts_draw.
+    fn offers_draw(&self, _b: &Board, _s: Side) -> bool {
+        false
+    }
+
+    // the player on side s has been offered a draw; true ends the game drawn.
+    fn accepts_draw(&self, _b: &Board, _s: Side) -> bool {
+        false
+    }
+
+    // called before choose_move, should_swap and the resign and draw questions in a timed game,
+    // with the game's time control and the time the player has left on its clock, for players
+    // that budget their thinking.
+    fn set_time_left(&self, _control: TimeControl, _remaining: Duration) {}
+
+    // what the player would say about the move it just chose (what its search thought of the
+    // position, say), for the game's observer to pass on; None if it has nothing to say.
+    fn explain_choice(&self) -> Option<String> {
+        None
+    }
 }
@@ -0,0 +1,668 @@
+#![allow(dead_code)]
+
+// The record of a game: where it started, who played, under which rules, every complete move
+// and how it ended. Records are saved as text in a format in the spirit of PGN for chess:
+//
+//     [South "Eric"]
+//     [North "Homer"]
+//     [Rules "kalah"]
+//     [Start "6/4,4,4,4,4,4/0/4,4,4,4,4,4/0/S"]
+//     [Result "South"]
+//
+//     1. S 3 6
+//     swap
+//     2. N 2x5
+//     ...
+//     sweep 12 from S to S
+//
+// The tags come first, one per line; Start uses the position notation from notation.rs and Result
+// is South, North, Draw or * for a game that is not over. A game that ended other than by being
+// played out has a Termination tag saying how: "illegal move by North", "time forfeit by South",
+// "resignation by North", "draw agreed", "majority" or "move limit". Then comes one complete move
+// per line: an optional move number, the side that moved, and the holes it sowed in order (more
+// than one when a move earned extra turns), each written as a Move (so with cw or ccw after it
+// under rules where players pick the direction) and followed by xN if it captured N beans. A line
+// reading "swap" marks the players trading sides under the pie rule, and the last line describes
+// the end-of-game sweep, if there was one. Blank lines and lines starting with ';' are ignored.
+// The names in the tags are those of the players who started on each side. Inside a tag's quotes,
+// a quote, a backslash and a line break are written \", \\ and \n.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::board::{Board, Move, Side, Sweep};
+use crate::notation::{parse_side_letter, side_letter, Position};
+use crate::rules::Rules;
+
+// how a game that is over came to an end.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Termination {
+    // it was played out until the beans left in play were swept into a pot.
+    Normal,
+    // side lost by forfeit for choosing moves it could not play (see IllegalMovePolicy).
+    IllegalMove(Side),
+    // side lost because its clock ran out (see Game::set_clock).
+    TimeForfeit(Side),
+    // side resigned.
+    Resignation(Side),
+    // the players agreed to a draw.
+    DrawAgreed,
+    // a pot held more than half of the beans, so its owner had won whatever came next (see
+    // Adjudication).
+    Majority,
+    // the game reached its move limit and was decided by the pots (see Adjudication).
+    MoveLimit,
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Termination::Normal => write!(f, "normal"),
+            Termination::IllegalMove(side) => write!(f, "illegal move by {:?}", side),
+            Termination::TimeForfeit(side) => write!(f, "time forfeit by {:?}", side),
+            Termination::Resignation(side) => write!(f, "resignation by {:?}", side),
+            Termination::DrawAgreed => write!(f, "draw agreed"),
+            Termination::Majority => write!(f, "majority"),
+            Termination::MoveLimit => write!(f, "move limit"),
+        }
+    }
+}
+
+impl FromStr for Termination {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let by = |prefix: &str| match text.strip_prefix(prefix) {
+            Some("South") => Some(Side::South),
+            Some("North") => Some(Side::North),
+            _ => None,
+        };
+        match text {
+            "normal" => return Ok(Termination::Normal),
+            "draw agreed" => return Ok(Termination::DrawAgreed),
+            "majority" => return Ok(Termination::Majority),
+            "move limit" => return Ok(Termination::MoveLimit),
+            _ => {}
+        }
+        if let Some(side) = by("illegal move by ") {
+            Ok(Termination::IllegalMove(side))
+        } else if let Some(side) = by("time forfeit by ") {
+            Ok(Termination::TimeForfeit(side))
+        } else if let Some(side) = by("resignation by ") {
+            Ok(Termination::Resignation(side))
+        } else {
+            Err(format!("bad termination {}", text))
+        }
+    }
+}
+
+// one hole sown as part of a complete move, and the beans it captured.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Sowing {
+    pub mv: Move,
+    pub captured: i32,
+}
+
+// everything one side did in one turn.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MoveRecord {
+    pub side: Side,
+    pub sowings: Vec<Sowing>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    pub south: String,
+    pub north: String,
+    pub rules: Rules,
+    // the starting board and the side that moved first
+    pub start: Board,
+    pub first_to_move: Side,
+    // true if the players swapped sides under the pie rule after the first move
+    pub swapped: bool,
+    pub moves: Vec<MoveRecord>,
+    // the sweep that ended the game, if it is over
+    pub sweep: Option<Sweep>,
+    // None while the game is not over; Some(None) for a tie
+    pub result: Option<Option<Side>>,
+    // how the game ended; None while it is not over
+    pub termination: Option<Termination>,
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Io(io::Error),
+    // the text is not a valid record; line numbers start at 1
+    Syntax { line: usize, message: String },
+    // the moves cannot be played from the start position as recorded; moves are numbered
+    // from 1, with 0 meaning the end of the game
+    Replay { move_number: usize, message: String },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Io(err) => write!(f, "{}", err),
+            RecordError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            RecordError::Replay {
+                move_number,
+                message,
+            } => write!(f, "move {}: {}", move_number, message),
+        }
+    }
+}
+
+impl From<io::Error> for RecordError {
+    fn from(err: io::Error) -> Self {
+        RecordError::Io(err)
+    }
+}
+
+impl GameRecord {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<GameRecord, RecordError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    // plays the recorded moves from the start position, checking every move is legal and does
+    // what the record says, and returns the final board.
+    pub fn replay(&self) -> Result<Board, RecordError> {
+        let mut board = self.start.clone();
+        board.set_rules(self.rules);
+        let mut side_to_move = self.first_to_move;
+        let mut sweep = None;
+        // a game that ended early can stop in the middle of a turn.
+        let ended_early = matches!(self.termination, Some(t) if t != Termination::Normal);
+
+        for (i, record) in self.moves.iter().enumerate() {
+            let replay_error = |message: String| RecordError::Replay {
+                move_number: i + 1,
+                message,
+            };
+
+            if sweep.is_some() {
+                return Err(replay_error("the game is already over".to_owned()));
+            }
+            if record.side != side_to_move {
+                return Err(replay_error(format!(
+                    "{:?} moved, but it was {:?}'s turn",
+                    record.side, side_to_move
+                )));
+            }
+            if record.sowings.is_empty() {
+                return Err(replay_error("no holes were sown".to_owned()));
+            }
+
+            for (j, sowing) in record.sowings.iter().enumerate() {
+                if side_to_move != record.side || sweep.is_some() {
+                    return Err(replay_error(format!(
+                        "hole {} was sown after the turn had ended",
+                        sowing.mv
+                    )));
+                }
+                let outcome = board
+                    .check_sowing(record.side, sowing.mv)
+                    .and_then(|mv| board.apply_move(record.side, mv))
+                    .map_err(|e| replay_error(format!("hole {} is not legal: {}", sowing.mv, e)))?;
+                if outcome.captured != sowing.captured {
+                    return Err(replay_error(format!(
+                        "hole {} captures {}, not {}",
+                        sowing.mv, outcome.captured, sowing.captured
+                    )));
+                }
+                side_to_move = outcome.next_to_move;
+                sweep = outcome.sweep;
+
+                let last = j + 1 == record.sowings.len();
+                let last_move = i + 1 == self.moves.len();
+                if last && outcome.extra_turn && sweep.is_none() && !(last_move && ended_early) {
+                    return Err(replay_error(format!(
+                        "hole {} earns another turn that is not recorded",
+                        sowing.mv
+                    )));
+                }
+            }
+        }
+
+        // a game can also end at the start of a turn, before any hole is sown.
+        if sweep.is_none() && self.sweep.is_some() && board.is_game_over(side_to_move) {
+            sweep = Some(board.end_game(side_to_move));
+        }
+        if sweep != self.sweep {
+            return Err(RecordError::Replay {
+                move_number: 0,
+                message: format!("the game ends with {:?}, not {:?}", sweep, self.sweep),
+            });
+        }
+
+        Ok(board)
+    }
+}
+
+fn result_text(result: Option<Option<Side>>) -> &'static str {
+    match result {
+        None => "*",
+        Some(None) => "Draw",
+        Some(Some(Side::South)) => "South",
+        Some(Some(Side::North)) => "North",
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "[South \"{}\"]", escape(&self.south))?;
+        writeln!(f, "[North \"{}\"]", escape(&self.north))?;
+        writeln!(f, "[Rules \"{}\"]", self.rules)?;
+        writeln!(
+            f,
+            "[Start \"{}\"]",
+            Position::new(self.start.clone(), self.first_to_move)
+        )?;
+        writeln!(f, "[Result \"{}\"]", result_text(self.result))?;
+        if let Some(termination) = self.termination.filter(|&t| t != Termination::Normal) {
+            writeln!(f, "[Termination \"{}\"]", termination)?;
+        }
+        writeln!(f)?;
+
+        for (i, record) in self.moves.iter().enumerate() {
+            write!(f, "{}. {}", i + 1, side_letter(record.side))?;
+            for sowing in &record.sowings {
+                write!(f, " {}", sowing.mv)?;
+                if sowing.captured > 0 {
+                    write!(f, "x{}", sowing.captured)?;
+                }
+            }
+            writeln!(f)?;
+            if i == 0 && self.swapped {
+                writeln!(f, "swap")?;
+            }
+        }
+
+        if let Some(sweep) = self.sweep {
+            writeln!(
+                f,
+                "sweep {} from {} to {}",
+                sweep.beans,
+                side_letter(sweep.from_side),
+                side_letter(sweep.pot_owner)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// a tag value as it is written between the quotes.
+fn escape(value: &str) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// the tag value written between the quotes as escape writes it, or None if it is not.
+fn unescape(text: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                '"' => value.push('"'),
+                '\\' => value.push('\\'),
+                'n' => value.push('\n'),
+                _ => return None,
+            },
+            '"' => return None,
+            _ => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+fn parse_tag(line: &str) -> Option<(&str, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name, unescape(value)?))
+}
+
+fn parse_sowing(word: &str) -> Option<Sowing> {
+    let (hole, captured) = match word.split_once('x') {
+        Some((hole, captured)) => (hole, captured.parse().ok()?),
+        None => (word, 0),
+    };
+    Some(Sowing {
+        mv: hole.parse().ok()?,
+        captured,
+    })
+}
+
+fn parse_sweep(words: &[&str]) -> Option<Sweep> {
+    match words {
+        ["sweep", beans, "from", from, "to", to] => {
+            let from_side = parse_side_letter(from)?;
+            Some(Sweep {
+                empty_side: from_side.opponent(),
+                from_side,
+                pot_owner: parse_side_letter(to)?,
+                beans: beans.parse().ok()?,
+            })
+        }
+        _ => None,
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut south = None;
+        let mut north = None;
+        let mut rules = Rules::default();
+        let mut start = None;
+        let mut result = None;
+        let mut termination = None;
+        let mut swapped = false;
+        let mut moves = Vec::new();
+        let mut sweep = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let syntax_error = |message: String| RecordError::Syntax {
+                line: i + 1,
+                message,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            if sweep.is_some() {
+                return Err(syntax_error("nothing can follow the sweep".to_owned()));
+            }
+
+            if line.starts_with('[') {
+                if !moves.is_empty() {
+                    return Err(syntax_error("tags must come before the moves".to_owned()));
+                }
+                let (name, value) =
+                    parse_tag(line).ok_or_else(|| syntax_error(format!("bad tag {}", line)))?;
+                match name {
+                    "South" => south = Some(value),
+                    "North" => north = Some(value),
+                    "Rules" => rules = value.parse().map_err(syntax_error)?,
+                    "Start" => {
+                        let position: Position = value
+                            .parse()
+                            .map_err(|err| syntax_error(format!("bad start position: {}", err)))?;
+                        start = Some(position);
+                    }
+                    "Result" => {
+                        result = match value.as_str() {
+                            "*" => None,
+                            "Draw" => Some(None),
+                            "South" => Some(Some(Side::South)),
+                            "North" => Some(Some(Side::North)),
+                            _ => return Err(syntax_error(format!("bad result {}", value))),
+                        }
+                    }
+                    "Termination" => termination = Some(value.parse().map_err(syntax_error)?),
+                    // unknown tags are kept out of the way, as in PGN
+                    _ => {}
+                }
+                continue;
+            }
+
+            let mut words: Vec<&str> = line.split_whitespace().collect();
+            if words[0].ends_with('.') {
+                words.remove(0);
+            }
+            match words.as_slice() {
+                ["swap"] => {
+                    if moves.len() != 1 || swapped {
+                        return Err(syntax_error(
+                            "sides can only be swapped after the first move".to_owned(),
+                        ));
+                    }
+                    swapped = true;
+                }
+                ["sweep", ..] => {
+                    sweep = Some(
+                        parse_sweep(&words)
+                            .ok_or_else(|| syntax_error(format!("bad sweep {}", line)))?,
+                    );
+                }
+                [side, holes @ ..] => {
+                    let side = parse_side_letter(side)
+                        .ok_or_else(|| syntax_error(format!("bad side {}", side)))?;
+                    let sowings = holes
+                        .iter()
+                        .map(|word| {
+                            parse_sowing(word)
+                                .ok_or_else(|| syntax_error(format!("bad hole {}", word)))
+                        })
+                        .collect::<Result<Vec<Sowing>, RecordError>>()?;
+                    if sowings.is_empty() {
+                        return Err(syntax_error("a move needs at least one hole".to_owned()));
+                    }
+                    moves.push(MoveRecord { side, sowings });
+                }
+                [] => return Err(syntax_error("a move needs a side".to_owned())),
+            }
+        }
+
+        let missing = |tag: &str| RecordError::Syntax {
+            line: 0,
+            message: format!("missing {} tag", tag),
+        };
+        let start = start.ok_or_else(|| missing("Start"))?;
+        let mut board = start.board;
+        board.set_rules(rules);
+
+        Ok(GameRecord {
+            south: south.ok_or_else(|| missing("South"))?,
+            north: north.ok_or_else(|| missing("North"))?,
+            rules,
+            start: board,
+            first_to_move: start.side_to_move,
+            swapped,
+            moves,
+            sweep,
+            result,
+            // a finished game without the tag was played out.
+            termination: result.map(|_| termination.unwrap_or(Termination::Normal)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bad_player::BadPlayer;
+    use crate::game::Game;
+    use crate::rules::{CaptureRule, SweepRule};
+    use crate::smart_player::{SearchBudget, SmartPlayer};
+
+    fn played_game(board: Board, pie_rule: bool) -> Game {
+        let mut game = Game::new(
+            board,
+            Box::new(SmartPlayer::new_with_budget(
+                "Homer",
+                SearchBudget::depth(3),
+            )),
+            Box::new(BadPlayer::new_from_name("Bart")),
+        );
+        game.set_pie_rule(pie_rule);
+        game.play();
+        game
+    }
+
+    #[test]
+    fn saved_game_replays_to_the_same_board() {
+        let game = played_game(Board::new(6, 4), false);
+        let record = game.record();
+        assert!(record.result.is_some());
+        assert!(record.sweep.is_some());
+
+        let text = record.to_string();
+        let loaded: GameRecord = text.parse().unwrap();
+        assert_eq!(loaded.to_string(), text);
+        assert_eq!(loaded.replay().unwrap(), record.replay().unwrap());
+        assert_eq!(
+            loaded.replay().unwrap().to_string(),
+            game.record().replay().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn save_and_load_through_a_file() {
+        let rules = Rules::kalah()
+            .with_capture(CaptureRule::EvenIfOppositeEmpty)
+            .with_sweep(SweepRule::ToEmptySide);
+        let mut board = Board::new(4, 3);
+        board.set_rules(rules);
+        let game = played_game(board, true);
+        let record = game.record();
+
+        let path = std::env::temp_dir().join(format!("kalah-record-{}.txt", std::process::id()));
+        record.save(&path).unwrap();
+        let loaded = GameRecord::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.rules, rules);
+        assert_eq!(loaded.south, "Homer");
+        assert_eq!(loaded.north, "Bart");
+        assert_eq!(loaded.swapped, record.swapped);
+        assert_eq!(loaded.moves, record.moves);
+        assert_eq!(loaded.result, record.result);
+        assert_eq!(loaded.replay().unwrap(), record.replay().unwrap());
+    }
+
+    const SHORT_GAME: &str = "[South \"Eric\"]
+[North \"Homer\"]
+[Rules \"kalah\"]
+[Start \"2/1,1/0/0,1/0/S\"]
+[Result \"South\"]
+
+; South sows into its pot, then captures North's last bean
+1. S 2 1x2
+sweep 0 from S to S
+";
+
+    #[test]
+    fn parse_a_written_record() {
+        let record: GameRecord = SHORT_GAME.parse().unwrap();
+        assert_eq!(record.south, "Eric");
+        assert_eq!(record.first_to_move, Side::South);
+        assert_eq!(
+            record.moves,
+            vec![MoveRecord {
+                side: Side::South,
+                sowings: vec![
+                    Sowing {
+                        mv: Move::new(2).unwrap(),
+                        captured: 0
+                    },
+                    Sowing {
+                        mv: Move::new(1).unwrap(),
+                        captured: 2
+                    }
+                ],
+            }]
+        );
+        assert_eq!(record.result, Some(Some(Side::South)));
+        assert_eq!(record.termination, Some(Termination::Normal));
+
+        let board = record.replay().unwrap();
+        assert_eq!(board.to_string(), "2/0,0/3/0,0/0");
+    }
+
+    #[test]
+    fn records_that_do_not_replay() {
+        let replay_error = |text: String| match text.parse::<GameRecord>().unwrap().replay() {
+            Err(RecordError::Replay { message, .. }) => message,
+            other => panic!("expected a replay error, got {:?}", other),
+        };
+
+        // hole 2 earns another turn that is missing
+        let missing_turn = SHORT_GAME.replace("1. S 2 1x2", "1. S 2\n2. N 2");
+        assert!(replay_error(missing_turn).contains("another turn"));
+        // the capture is 2 beans
+        let wrong_capture = SHORT_GAME.replace("1x2", "1x3");
+        assert!(replay_error(wrong_capture).contains("captures"));
+        // hole 1 is sown while hole 2 is still empty... and North cannot move first
+        let wrong_side = SHORT_GAME.replace("1. S 2 1x2", "1. N 2");
+        assert!(replay_error(wrong_side).contains("turn"));
+        let illegal = SHORT_GAME.replace("1. S 2 1x2", "1. S 2 2");
+        assert!(replay_error(illegal).contains("not legal"));
+        let wrong_sweep = SHORT_GAME.replace("sweep 0 from S to S", "sweep 1 from S to S");
+        assert!(replay_error(wrong_sweep).contains("ends with"));
+    }
+
+    #[test]
+    fn awkward_names_read_back() {
+        let mut record: GameRecord = SHORT_GAME.parse().unwrap();
+        record.south = "Eric \"the Red\" \\o/".to_owned();
+        record.north = "Two\nLines ]".to_owned();
+        let text = record.to_string();
+        assert!(text.contains(r#"[South "Eric \"the Red\" \\o/"]"#));
+        assert!(text.contains(r#"[North "Two\nLines ]"]"#));
+
+        let loaded: GameRecord = text.parse().unwrap();
+        assert_eq!(loaded.south, record.south);
+        assert_eq!(loaded.north, record.north);
+        assert_eq!(loaded.to_string(), text);
+
+        let bad_escape = SHORT_GAME.replace("\"Eric\"", r#""Eric\t""#);
+        assert!(bad_escape.parse::<GameRecord>().is_err());
+        let bare_quote = SHORT_GAME.replace("\"Eric\"", r#""Er"ic""#);
+        assert!(bare_quote.parse::<GameRecord>().is_err());
+    }
+
+    #[test]
+    fn terminations_read_back() {
+        let terminations = [
+            Termination::Normal,
+            Termination::IllegalMove(Side::North),
+            Termination::TimeForfeit(Side::South),
+            Termination::Resignation(Side::North),
+            Termination::DrawAgreed,
+            Termination::Majority,
+            Termination::MoveLimit,
+        ];
+        for termination in terminations {
+            assert_eq!(termination.to_string().parse(), Ok(termination));
+        }
+        assert!("resignation by".parse::<Termination>().is_err());
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let syntax_line = |text: String| match text.parse::<GameRecord>() {
+            Err(RecordError::Syntax { line, .. }) => line,
+            other => panic!("expected a syntax error, got {:?}", other),
+        };
+        assert_eq!(syntax_line(SHORT_GAME.replace("[Start", "[Begin")), 0);
+        assert_eq!(syntax_line(SHORT_GAME.replace("2/1,1/0", "2/1,1,1/0")), 4);
+        assert_eq!(syntax_line(SHORT_GAME.replace("\"kalah\"", "\"chess\"")), 3);
+        assert_eq!(syntax_line(SHORT_GAME.replace("1x2", "1y2")), 8);
+        assert_eq!(syntax_line(SHORT_GAME.replace("1. S", "1. W")), 8);
+        assert_eq!(syntax_line(SHORT_GAME.replace("from S", "from")), 9);
+        assert_eq!(syntax_line(format!("{}1. N 1\n", SHORT_GAME)), 10);
+        let bad_termination = "[Termination \"illegal move by East\"]\n[Result";
+        assert_eq!(
+            syntax_line(SHORT_GAME.replace("[Result", bad_termination)),
+            5
+        );
+    }
+}
@@ -0,0 +1,318 @@
+#![allow(dead_code)]
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::Side;
+
+// The house rules a Board is played under. Board::apply_move and Board::sweep consult these, so
+// the game and every searching player automatically play the same variant.
+
+// which game is being played.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Ruleset {
+    // Kalah: beans are sown through the mover's own pot, a last bean in that pot earns another
+    // turn, and captures follow the CaptureRule below.
+    Kalah,
+    // Oware (Abapa): the pots only hold captured beans and are not sown into, a hole holding 12
+    // or more beans skips itself as the sowing goes round, and a last bean that makes a hole on
+    // the opponent's side hold 2 or 3 captures it along with the unbroken run of 2s and 3s
+    // before it. A capture that would take all of the opponent's beans is not made (the "grand
+    // slam" rule), and a player whose opponent has no beans must make a move that gives them
+    // some.
+    Oware,
+}
+
+// what happens when the last bean of a move lands in one of the mover's own empty holes.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum CaptureRule {
+    // that bean and the beans in the opponent's opposite hole go to the mover's pot, but only if
+    // the opposite hole has beans in it.
+    Standard,
+    // the capture happens even if the opposite hole is empty (so only the landing bean moves).
+    EvenIfOppositeEmpty,
+    // nothing is ever captured.
+    NoCapture,
+}
+
+// where the beans still in play go when the side to move has nothing left to sow.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SweepRule {
+    // each side's remaining beans go to that side's own pot. Only the side that did not run out
+    // has any, so they go to the opponent of the player who ran out.
+    ToOwner,
+    // the remaining beans go to the pot of the player who ran out.
+    ToEmptySide,
+}
+
+// which way round the board beans are sown. With North's side at the top, counter-clockwise is
+// South sowing from hole 1 towards hole n and North from hole n towards hole 1.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Direction {
+    CounterClockwise,
+    Clockwise,
+}
+
+impl Direction {
+    pub fn reversed(&self) -> Direction {
+        match self {
+            Direction::CounterClockwise => Direction::Clockwise,
+            Direction::Clockwise => Direction::CounterClockwise,
+        }
+    }
+}
+
+// which way the players sow.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SowingRule {
+    // every move goes the same way.
+    Always(Direction),
+    // each move says which way it goes (see Move::towards).
+    PlayersChoice,
+}
+
+// which pots (stores) the sowing path passes through. A store that is not on the path only
+// holds captured and swept beans.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum StoreRule {
+    // only the sower's own pot; a last bean landing there earns another turn.
+    Own,
+    // both pots, whoever is sowing.
+    Both,
+    // neither pot.
+    Neither,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Rules {
+    pub ruleset: Ruleset,
+    // only used by Kalah; Oware has its own captures.
+    pub capture: CaptureRule,
+    pub sweep: SweepRule,
+    pub sowing: SowingRule,
+    pub stores: StoreRule,
+}
+
+impl Rules {
+    // the standard rules of Kalah.
+    pub fn kalah() -> Self {
+        Rules {
+            ruleset: Ruleset::Kalah,
+            capture: CaptureRule::Standard,
+            sweep: SweepRule::ToOwner,
+            sowing: SowingRule::Always(Direction::CounterClockwise),
+            stores: StoreRule::Own,
+        }
+    }
+
+    // the Abapa rules of Oware. When a player cannot be given any beans, the game ends and the
+    // other player keeps the beans on their side.
+    pub fn oware() -> Self {
+        Rules {
+            ruleset: Ruleset::Oware,
+            capture: CaptureRule::Standard,
+            sweep: SweepRule::ToOwner,
+            sowing: SowingRule::Always(Direction::CounterClockwise),
+            stores: StoreRule::Neither,
+        }
+    }
+
+    // whether beans sown by player_sowing go into pot_owner's pot as they pass it.
+    pub fn sows_into_pot(&self, pot_owner: Side, player_sowing: Side) -> bool {
+        match self.stores {
+            StoreRule::Own => pot_owner == player_sowing,
+            StoreRule::Both => true,
+            StoreRule::Neither => false,
+        }
+    }
+
+    // whether a hole being emptied is skipped when the sowing comes back round to it.
+    pub fn skips_origin(&self) -> bool {
+        self.ruleset == Ruleset::Oware
+    }
+
+    // whether a player must give beans to an opponent who has none.
+    pub fn must_feed(&self) -> bool {
+        self.ruleset == Ruleset::Oware
+    }
+
+    pub fn with_capture(self, capture: CaptureRule) -> Self {
+        Rules { capture, ..self }
+    }
+
+    pub fn with_sweep(self, sweep: SweepRule) -> Self {
+        Rules { sweep, ..self }
+    }
+
+    pub fn with_sowing(self, sowing: SowingRule) -> Self {
+        Rules { sowing, ..self }
+    }
+
+    pub fn with_stores(self, stores: StoreRule) -> Self {
+        Rules { stores, ..self }
+    }
+
+    // the stores the ruleset's own rules sow into.
+    fn default_stores(&self) -> StoreRule {
+        match self.ruleset {
+            Ruleset::Kalah => StoreRule::Own,
+            Ruleset::Oware => StoreRule::Neither,
+        }
+    }
+
+    // a number identifying these rules, mixed into position keys so that search results under
+    // one set of rules are never reused under another.
+    pub fn id(&self) -> u64 {
+        let ruleset = match self.ruleset {
+            Ruleset::Kalah => 0,
+            Ruleset::Oware => 1,
+        };
+        let capture = match self.capture {
+            CaptureRule::Standard => 0,
+            CaptureRule::EvenIfOppositeEmpty => 1,
+            CaptureRule::NoCapture => 2,
+        };
+        let sweep = match self.sweep {
+            SweepRule::ToOwner => 0,
+            SweepRule::ToEmptySide => 1,
+        };
+        let sowing = match self.sowing {
+            SowingRule::Always(Direction::CounterClockwise) => 0,
+            SowingRule::Always(Direction::Clockwise) => 1,
+            SowingRule::PlayersChoice => 2,
+        };
+        let stores = match self.stores {
+            StoreRule::Own => 0,
+            StoreRule::Both => 1,
+            StoreRule::Neither => 2,
+        };
+        capture | sweep << 4 | ruleset << 8 | sowing << 12 | stores << 16
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::kalah()
+    }
+}
+
+// rules are written as the ruleset's name followed by any options that differ from the
+// defaults, e.g. "kalah", "kalah capture=empty sweep=empty-side", "oware" or
+// "kalah sowing=either stores=both".
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.ruleset {
+            Ruleset::Kalah => {
+                write!(f, "kalah")?;
+                match self.capture {
+                    CaptureRule::Standard => {}
+                    CaptureRule::EvenIfOppositeEmpty => write!(f, " capture=empty")?,
+                    CaptureRule::NoCapture => write!(f, " capture=none")?,
+                }
+            }
+            Ruleset::Oware => write!(f, "oware")?,
+        }
+        match self.sweep {
+            SweepRule::ToOwner => {}
+            SweepRule::ToEmptySide => write!(f, " sweep=empty-side")?,
+        }
+        match self.sowing {
+            SowingRule::Always(Direction::CounterClockwise) => {}
+            SowingRule::Always(Direction::Clockwise) => write!(f, " sowing=clockwise")?,
+            SowingRule::PlayersChoice => write!(f, " sowing=either")?,
+        }
+        if self.stores != self.default_stores() {
+            match self.stores {
+                StoreRule::Own => write!(f, " stores=own")?,
+                StoreRule::Both => write!(f, " stores=both")?,
+                StoreRule::Neither => write!(f, " stores=none")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Rules {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut words = text.split_whitespace();
+        let mut rules = match words.next() {
+            Some("kalah") => Rules::kalah(),
+            Some("oware") => Rules::oware(),
+            Some(other) => return Err(format!("unknown ruleset '{}'", other)),
+            None => return Err("no ruleset given".to_owned()),
+        };
+
+        for word in words {
+            rules = match word.split_once('=') {
+                Some(("capture", "standard")) => rules.with_capture(CaptureRule::Standard),
+                Some(("capture", "empty")) => rules.with_capture(CaptureRule::EvenIfOppositeEmpty),
+                Some(("capture", "none")) => rules.with_capture(CaptureRule::NoCapture),
+                Some(("sweep", "owner")) => rules.with_sweep(SweepRule::ToOwner),
+                Some(("sweep", "empty-side")) => rules.with_sweep(SweepRule::ToEmptySide),
+                Some(("sowing", "counter-clockwise")) => {
+                    rules.with_sowing(SowingRule::Always(Direction::CounterClockwise))
+                }
+                Some(("sowing", "clockwise")) => {
+                    rules.with_sowing(SowingRule::Always(Direction::Clockwise))
+                }
+                Some(("sowing", "either")) => rules.with_sowing(SowingRule::PlayersChoice),
+                Some(("stores", "own")) => rules.with_stores(StoreRule::Own),
+                Some(("stores", "both")) => rules.with_stores(StoreRule::Both),
+                Some(("stores", "none")) => rules.with_stores(StoreRule::Neither),
+                _ => return Err(format!("unknown rule option '{}'", word)),
+            };
+        }
+        Ok(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn rules_round_trip_through_text() {
+        let all = [
+            Rules::kalah(),
+            Rules::kalah().with_capture(CaptureRule::EvenIfOppositeEmpty),
+            Rules::kalah()
+                .with_capture(CaptureRule::NoCapture)
+                .with_sweep(SweepRule::ToEmptySide),
+            Rules::oware(),
+            Rules::oware().with_sweep(SweepRule::ToEmptySide),
+            Rules::kalah().with_sowing(SowingRule::Always(Direction::Clockwise)),
+            Rules::kalah()
+                .with_sowing(SowingRule::PlayersChoice)
+                .with_stores(StoreRule::Both),
+            Rules::kalah().with_stores(StoreRule::Neither),
+            Rules::oware().with_stores(StoreRule::Own),
+        ];
+        for rules in all {
+            assert_eq!(rules.to_string().parse::<Rules>(), Ok(rules));
+        }
+        assert_eq!(Rules::kalah().to_string(), "kalah");
+        assert_eq!(Rules::oware().to_string(), "oware");
+        assert_eq!(
+            Rules::oware().with_stores(StoreRule::Own).to_string(),
+            "oware stores=own"
+        );
+        assert_eq!(
+            "kalah sweep=empty-side capture=none".parse::<Rules>(),
+            Ok(Rules::kalah()
+                .with_capture(CaptureRule::NoCapture)
+                .with_sweep(SweepRule::ToEmptySide))
+        );
+    }
+
+    #[test]
+    fn bad_rules_text() {
+        assert!("".parse::<Rules>().is_err());
+        assert!("chess".parse::<Rules>().is_err());
+        assert!("kalah capture=sometimes".parse::<Rules>().is_err());
+        assert!("kalah fast".parse::<Rules>().is_err());
+        assert!("kalah sowing=sideways".parse::<Rules>().is_err());
+    }
+}
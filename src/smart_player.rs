@@ -1,76 +1,223 @@
 #![allow(dead_code)]
 
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
+use std::time::{Duration, Instant};
 
-use crate::board::Board;
 use crate::board::Side;
+use crate::board::{Board, CompactBoard, Move};
+use crate::clock::TimeControl;
+use crate::pits::Pits;
 use crate::player::Player;
+use crate::transposition::{Bound, Entry, TranspositionTable};
 pub struct SmartPlayer {
     name: String,
+    budget: SearchBudget,
+    // kept between moves: positions searched for one move often come up again in the next.
+    table: RefCell<TranspositionTable>,
+    // what the search for the last move found, for explain_choice.
+    last_search: RefCell<Option<(i32, i32)>>,
+    // in a timed game, the time control and the time left on our clock (see set_time_left).
+    time_left: Cell<Option<(TimeControl, Duration)>>,
+    // the position should_resign last searched, with what it found, so that offers_draw and
+    // choose_move for the same turn do not search it again.
+    turn_search: RefCell<Option<(Board, Side, Searched)>>,
+}
+
+// how much work SmartPlayer may spend on a single move. The search deepens one ply at a time
+// until one of the limits is hit, and then plays the best move of the last depth it finished.
+// Depth 1 is always finished, so a move is found even with a zero budget.
+#[derive(Copy, Clone, Debug)]
+pub struct SearchBudget {
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+    pub max_depth: Option<i32>,
+}
+
+impl SearchBudget {
+    pub fn time(time: Duration) -> Self {
+        SearchBudget {
+            time: Some(time),
+            nodes: None,
+            max_depth: None,
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        SearchBudget {
+            time: None,
+            nodes: Some(nodes),
+            max_depth: None,
+        }
+    }
+
+    pub fn depth(max_depth: i32) -> Self {
+        SearchBudget {
+            time: None,
+            nodes: None,
+            max_depth: Some(max_depth),
+        }
+    }
+}
+
+impl Default for SearchBudget {
+    fn default() -> Self {
+        SearchBudget::time(Duration::from_millis(500))
+    }
 }
 
 impl SmartPlayer {
     pub fn new_from_name(name: &str) -> Self {
+        SmartPlayer::new_with_budget(name, SearchBudget::default())
+    }
+
+    pub fn new_with_budget(name: &str, budget: SearchBudget) -> Self {
         SmartPlayer {
             name: name.to_owned(),
+            budget,
+            table: RefCell::new(TranspositionTable::new(TABLE_SIZE_LOG2)),
+            last_search: RefCell::new(None),
+            time_left: Cell::new(None),
+            turn_search: RefCell::new(None),
+        }
+    }
+
+    // the budget for the next search: ours, cut down in a timed game to what the clock allows.
+    fn budget(&self) -> SearchBudget {
+        let mut budget = self.budget;
+        if let Some((control, remaining)) = self.time_left.get() {
+            let allowed = time_for_move(control, remaining);
+            budget.time = Some(budget.time.map_or(allowed, |time| time.min(allowed)));
+        }
+        budget
+    }
+
+    // the budget for answering a draw offer: ours, but never more than a short search, since
+    // the answer is given on the time of the side that offered it.
+    fn draw_answer_budget(&self) -> SearchBudget {
+        let mut budget = self.budget();
+        budget.nodes = Some(
+            budget
+                .nodes
+                .map_or(DRAW_ANSWER_NODES, |n| n.min(DRAW_ANSWER_NODES)),
+        );
+        budget
+    }
+
+    // what iterative_deepening finds for side s to play in b, reusing the search should_resign
+    // made of the same position this turn.
+    fn search_turn(&self, b: &Board, s: Side) -> Searched {
+        if let Some((board, side, searched)) = self.turn_search.borrow().as_ref() {
+            if board == b && *side == s {
+                return *searched;
+            }
+        }
+        iterative_deepening(b, s, &self.budget(), Some(&mut self.table.borrow_mut()))
+    }
+}
+
+// how long to search for one move with `remaining` left on the clock. With time per move most of
+// it can go; with time for the whole game, a twentieth of it and most of the increment, but
+// never more than half of what is left. Either way a margin is left, because the search only
+// looks at the clock every so often and the first depth is always finished.
+fn time_for_move(control: TimeControl, remaining: Duration) -> Duration {
+    match control {
+        TimeControl::PerMove(_) => remaining * 3 / 4,
+        TimeControl::Increment { increment, .. } => {
+            (remaining / 20 + increment * 3 / 4).min(remaining / 2)
         }
     }
 }
 
+// the most nodes SmartPlayer searches to answer a draw offer.
+const DRAW_ANSWER_NODES: u64 = 20_000;
+
+// SmartPlayer's transposition table has 2^TABLE_SIZE_LOG2 slots.
+const TABLE_SIZE_LOG2: u32 = 18;
+
+// how often (in nodes) the search looks at the clock.
+const NODES_BETWEEN_TIME_CHECKS: u64 = 1024;
+
+// the evaluations of a position won by South and one won by North. Each is the other negated, so
+// a position and its mirror (see Board::mirrored) always have opposite values.
+const SOUTH_WINS: i32 = i32::MAX;
+const NORTH_WINS: i32 = -i32::MAX;
+
+#[derive(Copy, Clone)]
 struct BoardEval {
     eval: i32,
-    best_move: i32,
+    // None if no move was looked at: the game is over or the depth was 0
+    best_move: Option<Move>,
 }
 
-fn compute_heuristic(b: &Board) -> i32 {
+fn compute_heuristic<P: Pits>(b: &Board<P>) -> i32 {
     if b.beans(Side::North, 0) > b.total_beans() / 2 {
-        i32::MIN
+        NORTH_WINS
     } else if b.beans(Side::South, 0) > b.total_beans() / 2 {
-        i32::MAX
+        SOUTH_WINS
     } else {
         b.beans(Side::South, 0) - b.beans(Side::North, 0)
     }
 }
 
-// evaluates a board recursively using the minimax algorithm defined in the spec
-// b is a copy of the board, and evaluation_depth represents how much further we would like to go.
-// returns a positive integer if the board is good for South, and a negative integer if the board
-// is good for North.
-fn evaluate_board(b: Board, evaluation_depth: i32, side_to_play: Side) -> BoardEval {
-    if b.beans_in_play(side_to_play) == 0 {
-        // no legal moves, the game is over. Returns i32::MAX if south has won, and i32::MIN if north has won. return 0 if it is a tie.
-
-        let beans_i_have = b.beans(side_to_play, 0);
-        let total_beans = b.total_beans();
-
-        let eval = match beans_i_have.cmp(&(total_beans / 2)) {
-            Ordering::Equal => 0,
-            Ordering::Greater => {
-                if side_to_play == Side::South {
-                    i32::MAX
-                } else {
-                    i32::MIN
-                }
-            }
-            Ordering::Less => {
-                if side_to_play == Side::South {
-                    i32::MIN
-                } else {
-                    i32::MAX
-                }
-            }
-        };
+// evaluation of a board where side_to_play has no legal moves, so the game is over.
+// Returns SOUTH_WINS if south has won, and NORTH_WINS if north has won. return 0 if it is a tie.
+fn game_over_eval<P: Pits>(b: &Board<P>, side_to_play: Side) -> i32 {
+    // Board::apply_move has normally swept the remaining beans already; only a position that
+    // started out finished still needs it.
+    if b.beans_in_play(Side::North) + b.beans_in_play(Side::South) > 0 {
+        let mut swept = b.clone();
+        swept.end_game(side_to_play);
+        return game_over_eval(&swept, side_to_play);
+    }
+
+    match b.beans(Side::South, 0).cmp(&b.beans(Side::North, 0)) {
+        Ordering::Equal => 0,
+        Ordering::Greater => SOUTH_WINS,
+        Ordering::Less => NORTH_WINS,
+    }
+}
+
+// the transposition table is keyed by Board::canonical_key, so a position and its mirror share an
+// entry, which holds what the search found for the one with South to move. For North to move the
+// entry is turned round on the way in and out: the value is negated (swapping the bound) and the
+// best move mirrored. Turning it round twice gives back the entry.
+fn oriented(entry: Entry, side_to_play: Side, num_holes: i32) -> Entry {
+    if side_to_play == Side::South {
+        return entry;
+    }
+    Entry {
+        value: -entry.value,
+        bound: match entry.bound {
+            Bound::Exact => Bound::Exact,
+            Bound::Lower => Bound::Upper,
+            Bound::Upper => Bound::Lower,
+        },
+        best_move: entry.best_move.map(|mv| mv.mirrored(num_holes)),
+        ..entry
+    }
+}
 
+// evaluates a board recursively using the minimax algorithm defined in the spec
+// b is the board (left as it was found), and evaluation_depth represents how much further we
+// would like to go. returns a positive integer if the board is good for South, and a negative
+// integer if the board is good for North.
+fn evaluate_board<P: Pits>(
+    b: &mut Board<P>,
+    evaluation_depth: i32,
+    side_to_play: Side,
+) -> BoardEval {
+    if b.is_game_over(side_to_play) {
         return BoardEval {
-            eval,
-            best_move: -1,
+            eval: game_over_eval(b, side_to_play),
+            best_move: None,
         };
     }
 
     if evaluation_depth == 0 {
         return BoardEval {
-            eval: compute_heuristic(&b),
-            best_move: 1,
+            eval: compute_heuristic(b),
+            best_move: None,
         };
     }
 
@@ -80,55 +227,307 @@ fn evaluate_board(b: Board, evaluation_depth: i32, side_to_play: Side) -> BoardE
         // north is the minimizing player
         Side::North => i32::MAX,
     };
-    let mut best_move = -1;
-
-    for hole in 1..=b.holes() {
-        if b.beans(side_to_play, hole) == 0 {
-            continue;
-        }
-        let mut board_clone = b.clone();
-
-        let mut end_side: Side = Side::North;
-        let mut end_hole: i32 = 0;
-        board_clone.sow(side_to_play, hole, &mut end_side, &mut end_hole);
-
-        // execute a capture if the move ends in a player's own empty hole, and the opponent's hole
-        // opposite the ending hole is nonempty:
-        #[allow(clippy::collapsible_if)]
-        if end_hole != 0 && end_side == side_to_play {
-            if board_clone.beans(side_to_play, end_hole) == 1 {
-                // if the hole used to be empty
-                if board_clone.beans(side_to_play.opponent(), end_hole) != 0 {
-                    // if the opponent's corresponding hole is nonempty, execute capture
-                    board_clone.move_to_pot(side_to_play, end_hole, side_to_play);
-                    board_clone.move_to_pot(side_to_play.opponent(), end_hole, side_to_play);
+    let mut best_move = None;
+
+    let moves: Vec<Move> = b.legal_moves(side_to_play).collect();
+    for mv in moves {
+        let undo = b.make_move(side_to_play, mv).unwrap();
+        let BoardEval { eval, best_move: _ } =
+            evaluate_board(b, evaluation_depth - 1, undo.outcome.next_to_move);
+        b.unmake_move(&undo);
+
+        match side_to_play {
+            Side::South => {
+                if eval >= best_value {
+                    best_value = eval;
+                    best_move = Some(mv);
+                }
+            }
+            Side::North => {
+                if eval <= best_value {
+                    best_value = eval;
+                    best_move = Some(mv);
                 }
             }
         }
+    }
 
-        let next_player = if end_hole == 0 {
-            side_to_play
-        } else {
-            side_to_play.opponent()
-        };
+    BoardEval {
+        eval: best_value,
+        best_move,
+    }
+}
 
-        let BoardEval { eval, best_move: _ } =
-            evaluate_board(board_clone, evaluation_depth - 1, next_player);
+// state carried through one alpha-beta search: the best move found so far at each ply (tried
+// early in sibling subtrees, where it is often good again), the number of nodes visited, the
+// limits that make the search give up part way, and optionally a transposition table.
+struct SearchContext<'a> {
+    killers: Vec<Option<Move>>,
+    table: Option<&'a mut TranspositionTable>,
+    nodes: u64,
+    deadline: Option<Instant>,
+    node_limit: Option<u64>,
+    // set once a limit is hit; every search function returns immediately after that, and the
+    // (meaningless) result of the unfinished depth must be thrown away.
+    aborted: bool,
+    // set if some line was cut off by the depth limit rather than by the end of the game, i.e.
+    // searching deeper could still change the answer.
+    hit_depth_limit: bool,
+}
+
+impl<'a> SearchContext<'a> {
+    fn new() -> Self {
+        SearchContext {
+            killers: Vec::new(),
+            table: None,
+            nodes: 0,
+            deadline: None,
+            node_limit: None,
+            aborted: false,
+            hit_depth_limit: false,
+        }
+    }
+
+    fn with_budget(budget: &SearchBudget, table: Option<&'a mut TranspositionTable>) -> Self {
+        let mut ctx = SearchContext::new();
+        ctx.table = table;
+        ctx.deadline = budget.time.map(|t| Instant::now() + t);
+        ctx.node_limit = budget.nodes;
+        ctx
+    }
+
+    fn visit_node(&mut self) {
+        self.nodes += 1;
+        if let Some(limit) = self.node_limit {
+            if self.nodes > limit {
+                self.aborted = true;
+            }
+        }
+        if let Some(deadline) = self.deadline {
+            if self.nodes.is_multiple_of(NODES_BETWEEN_TIME_CHECKS) && Instant::now() >= deadline {
+                self.aborted = true;
+            }
+        }
+    }
+}
+
+// the legal moves in b in the order they should be searched: moves that earn another turn first,
+// then captures (biggest first), then the previous best move, then the rest by hole. What each
+// move does is guessed (see Board::guess_move) rather than found by making it.
+fn ordered_moves<P: Pits>(
+    b: &Board<P>,
+    side_to_play: Side,
+    previous_best: Option<Move>,
+) -> Vec<Move> {
+    let mut moves: Vec<(Move, (i32, i32))> = b
+        .legal_moves(side_to_play)
+        .map(|mv| {
+            let (extra_turn, captured) = b.guess_move(side_to_play, mv);
+            let priority = if extra_turn {
+                0
+            } else if captured > 0 {
+                1
+            } else if Some(mv) == previous_best {
+                2
+            } else {
+                3
+            };
+            (mv, (priority, -captured))
+        })
+        .collect();
+
+    moves.sort_by_key(|&(_, order)| order);
+    moves.into_iter().map(|(mv, _)| mv).collect()
+}
+
+// the value of b under the same minimax as evaluate_board, with alpha-beta pruning. The result is
+// exact whenever it lies strictly between alpha and beta; otherwise it is a bound on the side of
+// the window it fell out of (fail-soft).
+// b is left as it was found.
+fn alpha_beta<P: Pits>(
+    b: &mut Board<P>,
+    evaluation_depth: i32,
+    side_to_play: Side,
+    mut alpha: i32,
+    mut beta: i32,
+    ply: usize,
+    ctx: &mut SearchContext,
+) -> i32 {
+    ctx.visit_node();
+    if ctx.aborted {
+        return 0;
+    }
+
+    if b.is_game_over(side_to_play) {
+        return game_over_eval(b, side_to_play);
+    }
+    if evaluation_depth == 0 {
+        ctx.hit_depth_limit = true;
+        return compute_heuristic(b);
+    }
+
+    if ctx.killers.len() <= ply {
+        ctx.killers.resize(ply + 1, None);
+    }
+    let mut previous_best = ctx.killers[ply];
+
+    let key = b.canonical_key(side_to_play);
+    let probed = ctx.table.as_ref().and_then(|table| table.probe(key));
+    if let Some(entry) = probed.map(|entry| oriented(entry, side_to_play, b.holes())) {
+        // only results of a search to exactly this depth are reused (or of a shallower one that
+        // saw the end of every line), so the value is still what a plain minimax would give.
+        if entry.depth == evaluation_depth || (entry.solved && entry.depth < evaluation_depth) {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.value >= beta,
+                Bound::Upper => entry.value <= alpha,
+            };
+            if cutoff {
+                ctx.hit_depth_limit |= !entry.solved;
+                return entry.value;
+            }
+        }
+        previous_best = entry.best_move;
+    }
+
+    let (original_alpha, original_beta) = (alpha, beta);
+    // track whether this subtree on its own is cut off by the depth limit.
+    let outer_hit_depth_limit = ctx.hit_depth_limit;
+    ctx.hit_depth_limit = false;
+
+    let mut best_value = match side_to_play {
+        Side::South => i32::MIN,
+        Side::North => i32::MAX,
+    };
+    let mut best_move = None;
+
+    for mv in ordered_moves(b, side_to_play, previous_best) {
+        let undo = b.make_move(side_to_play, mv).unwrap();
+        let eval = alpha_beta(
+            b,
+            evaluation_depth - 1,
+            undo.outcome.next_to_move,
+            alpha,
+            beta,
+            ply + 1,
+            ctx,
+        );
+        b.unmake_move(&undo);
+        if ctx.aborted {
+            return 0;
+        }
 
         match side_to_play {
             Side::South => {
-                if eval >= best_value {
+                if eval > best_value || best_move.is_none() {
                     best_value = eval;
-                    best_move = hole;
+                    best_move = Some(mv);
                 }
+                alpha = alpha.max(eval);
             }
             Side::North => {
-                if eval <= best_value {
+                if eval < best_value || best_move.is_none() {
                     best_value = eval;
-                    best_move = hole;
+                    best_move = Some(mv);
                 }
+                beta = beta.min(eval);
             }
         }
+
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    let solved = !ctx.hit_depth_limit;
+    ctx.hit_depth_limit |= outer_hit_depth_limit;
+
+    ctx.killers[ply] = best_move;
+    if let Some(table) = ctx.table.as_mut() {
+        let bound = if best_value <= original_alpha {
+            Bound::Upper
+        } else if best_value >= original_beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        let entry = Entry {
+            key,
+            depth: evaluation_depth,
+            value: best_value,
+            bound,
+            best_move,
+            solved,
+        };
+        table.store(oriented(entry, side_to_play, b.holes()));
+    }
+    best_value
+}
+
+// searches b with alpha-beta and returns the same evaluation and move as evaluate_board at the
+// same depth, including its tie-break (the last legal move among equally good ones, i.e. the
+// highest-numbered hole). previous_best is tried early if it is not already an extra-turn move or
+// a capture.
+fn search<P: Pits>(
+    b: &mut Board<P>,
+    evaluation_depth: i32,
+    side_to_play: Side,
+    previous_best: Option<Move>,
+    ctx: &mut SearchContext,
+) -> BoardEval {
+    ctx.visit_node();
+
+    if b.is_game_over(side_to_play) {
+        return BoardEval {
+            eval: game_over_eval(b, side_to_play),
+            best_move: None,
+        };
+    }
+    if evaluation_depth == 0 {
+        ctx.hit_depth_limit = true;
+        return BoardEval {
+            eval: compute_heuristic(b),
+            best_move: None,
+        };
+    }
+
+    let mut best_value = match side_to_play {
+        Side::South => i32::MIN,
+        Side::North => i32::MAX,
+    };
+    let mut best_move = None;
+
+    for mv in ordered_moves(b, side_to_play, previous_best) {
+        // a move that only ties the best one so far still matters if it comes later, so the
+        // window is opened one past the best value to get ties back exactly.
+        let (alpha, beta) = match (side_to_play, best_move) {
+            (_, None) => (i32::MIN, i32::MAX),
+            (Side::South, _) => (best_value.saturating_sub(1), i32::MAX),
+            (Side::North, _) => (i32::MIN, best_value.saturating_add(1)),
+        };
+        let undo = b.make_move(side_to_play, mv).unwrap();
+        let eval = alpha_beta(
+            b,
+            evaluation_depth - 1,
+            undo.outcome.next_to_move,
+            alpha,
+            beta,
+            1,
+            ctx,
+        );
+        b.unmake_move(&undo);
+        if ctx.aborted {
+            break;
+        }
+
+        let better = match side_to_play {
+            Side::South => eval > best_value,
+            Side::North => eval < best_value,
+        };
+        if best_move.is_none_or(|best| better || (eval == best_value && mv > best)) {
+            best_value = eval;
+            best_move = Some(mv);
+        }
     }
 
     BoardEval {
@@ -137,6 +536,85 @@ fn evaluate_board(b: Board, evaluation_depth: i32, side_to_play: Side) -> BoardE
     }
 }
 
+// what iterative_deepening found: the result of the deepest search that finished, that depth, and
+// whether the search reached the end of the game on every line (so the evaluation is exact).
+type Searched = (BoardEval, i32, bool);
+
+// searches b one ply deeper at a time (each depth trying the previous depth's best move early)
+// until the budget runs out, and returns the result of the deepest search that finished along
+// with that depth. Stops early once a search reaches the end of the game on every line, since
+// going deeper could not change anything.
+fn iterative_deepening(
+    b: &Board,
+    side_to_play: Side,
+    budget: &SearchBudget,
+    table: Option<&mut TranspositionTable>,
+) -> Searched {
+    // the search makes and takes back moves on a board of its own, in the compact form if the
+    // board fits in it.
+    match CompactBoard::try_from(b) {
+        Ok(mut compact) => deepen(&mut compact, side_to_play, budget, table),
+        Err(_) => deepen(&mut b.clone(), side_to_play, budget, table),
+    }
+}
+
+fn deepen<P: Pits>(
+    b: &mut Board<P>,
+    side_to_play: Side,
+    budget: &SearchBudget,
+    table: Option<&mut TranspositionTable>,
+) -> Searched {
+    // depth 1 is searched without limits so that there is always a move to play.
+    let mut first = SearchContext::new();
+    let mut result = search(b, 1, side_to_play, None, &mut first);
+    let mut depth = 1;
+    let mut solved = !first.hit_depth_limit;
+
+    let mut ctx = SearchContext::with_budget(budget, table);
+    ctx.nodes = first.nodes;
+
+    while !solved && budget.max_depth.is_none_or(|max| depth < max) {
+        ctx.hit_depth_limit = false;
+        let attempt = search(b, depth + 1, side_to_play, result.best_move, &mut ctx);
+        if ctx.aborted {
+            break;
+        }
+        result = attempt;
+        depth += 1;
+        solved = !ctx.hit_depth_limit;
+    }
+
+    (result, depth, solved)
+}
+
+// searches the Kalah(6,4) opening to the given depth (without a transposition table) on a Board
+// and then on a CompactBoard, and prints the node rate of each.
+pub fn benchmark(depth: i32) {
+    let board = Board::new(6, 4);
+    let mut compact = CompactBoard::try_from(&board).unwrap();
+
+    let rates = [
+        ("Board", timed_search(&mut board.clone(), depth)),
+        ("CompactBoard", timed_search(&mut compact, depth)),
+    ];
+    for (name, (nodes, elapsed)) in rates {
+        println!(
+            "{:>12}: {} nodes in {:.3}s, {:.0} nodes/s",
+            name,
+            nodes,
+            elapsed.as_secs_f64(),
+            nodes as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
+
+fn timed_search<P: Pits>(b: &mut Board<P>, depth: i32) -> (u64, Duration) {
+    let start = Instant::now();
+    let mut ctx = SearchContext::new();
+    search(b, depth, Side::South, None, &mut ctx);
+    (ctx.nodes, start.elapsed())
+}
+
 impl Player for SmartPlayer {
     fn get_name(&self) -> String {
         self.name.clone()
@@ -146,15 +624,441 @@ impl Player for SmartPlayer {
         false
     }
 
-    fn choose_move(&self, b: &Board, s: Side) -> i32 {
-        // check for legal moves first. If none exists, return -1
-        if b.beans_in_play(s) == 0 {
-            return -1;
+    fn choose_move(&self, b: &Board, s: Side) -> Option<Move> {
+        // check for legal moves first. If none exists, return None
+        if b.is_game_over(s) {
+            return None;
+        }
+
+        let (BoardEval { eval, best_move }, depth, _) = self.search_turn(b, s);
+        self.turn_search.borrow_mut().take();
+        *self.last_search.borrow_mut() = Some((eval, depth));
+        b.check_sowing(s, best_move?).ok()
+    }
+
+    fn set_time_left(&self, control: TimeControl, remaining: Duration) {
+        self.time_left.set(Some((control, remaining)));
+    }
+
+    fn explain_choice(&self) -> Option<String> {
+        let (eval, depth) = (*self.last_search.borrow())?;
+        Some(format!(
+            "thinks the evaluation is currently {} (searched {} plies)",
+            eval, depth
+        ))
+    }
+
+    fn should_resign(&self, b: &Board, s: Side) -> bool {
+        // resign only once the search has proved that we lose whatever we do
+        let searched = self.search_turn(b, s);
+        *self.turn_search.borrow_mut() = Some((b.clone(), s, searched));
+        let (BoardEval { eval, .. }, _, _) = searched;
+        eval == match s {
+            Side::South => NORTH_WINS,
+            Side::North => SOUTH_WINS,
+        }
+    }
+
+    fn offers_draw(&self, b: &Board, s: Side) -> bool {
+        // offer a draw once the search has proved that neither side can do better
+        let (BoardEval { eval, .. }, _, solved) = self.search_turn(b, s);
+        solved && eval == 0
+    }
+
+    fn accepts_draw(&self, b: &Board, s: Side) -> bool {
+        // take the draw if the position (with the opponent, who offered it, to move) looks
+        // worse for us than for the opponent
+        let (BoardEval { eval, .. }, _, _) = iterative_deepening(
+            b,
+            s.opponent(),
+            &self.draw_answer_budget(),
+            Some(&mut self.table.borrow_mut()),
+        );
+        match s {
+            Side::North => eval > 0,
+            Side::South => eval < 0,
+        }
+    }
+
+    fn should_swap(&self, b: &Board, s: Side) -> bool {
+        // swap if the position (with us to move) is better for the opponent than for us
+        let (BoardEval { eval, .. }, _, _) =
+            iterative_deepening(b, s, &self.budget(), Some(&mut self.table.borrow_mut()));
+        match s {
+            Side::North => eval > 0,
+            Side::South => eval < 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::rng::Rng;
+    use crate::rules::{CaptureRule, Direction, Rules, SowingRule, StoreRule, SweepRule};
+
+    fn random_rules(rng: &mut Rng) -> Rules {
+        let sowing = match rng.range(0, 3) {
+            0 => SowingRule::PlayersChoice,
+            1 => SowingRule::Always(Direction::Clockwise),
+            _ => SowingRule::Always(Direction::CounterClockwise),
+        };
+        if rng.range(0, 3) == 0 {
+            return Rules::oware().with_sowing(sowing);
+        }
+        let capture = match rng.range(0, 2) {
+            0 => CaptureRule::Standard,
+            1 => CaptureRule::EvenIfOppositeEmpty,
+            _ => CaptureRule::NoCapture,
+        };
+        let sweep = match rng.range(0, 1) {
+            0 => SweepRule::ToOwner,
+            _ => SweepRule::ToEmptySide,
+        };
+        let stores = match rng.range(0, 3) {
+            0 => StoreRule::Both,
+            1 => StoreRule::Neither,
+            _ => StoreRule::Own,
+        };
+        Rules::kalah()
+            .with_capture(capture)
+            .with_sweep(sweep)
+            .with_sowing(sowing)
+            .with_stores(stores)
+    }
+
+    fn random_board(rng: &mut Rng) -> Board {
+        let rules = random_rules(rng);
+        // choosing the direction doubles the moves, so those boards are kept small enough for
+        // plain minimax.
+        let max_holes = match rules.sowing {
+            SowingRule::PlayersChoice => 3,
+            SowingRule::Always(_) => 6,
+        };
+        let mut b = Board::new_with_rules(rng.range(1, max_holes), 0, rules);
+        for hole in 1..=b.holes() {
+            b.set_beans(Side::North, hole, rng.range(0, 6)).unwrap();
+            b.set_beans(Side::South, hole, rng.range(0, 6)).unwrap();
+        }
+        b.set_beans(Side::North, 0, rng.range(0, 10)).unwrap();
+        b.set_beans(Side::South, 0, rng.range(0, 10)).unwrap();
+        b
+    }
+
+    #[test]
+    fn alpha_beta_agrees_with_minimax() {
+        let mut rng = Rng::new(2023);
+        for _ in 0..600 {
+            let mut b = random_board(&mut rng);
+            let side = if rng.range(0, 1) == 0 {
+                Side::South
+            } else {
+                Side::North
+            };
+            let depth = rng.range(0, 5);
+
+            let expected = evaluate_board(&mut b.clone(), depth, side);
+            let before = b.clone();
+            let actual = search(
+                &mut b,
+                depth,
+                side,
+                Move::new(rng.range(0, 6)).ok(),
+                &mut SearchContext::new(),
+            );
+            assert_eq!(actual.eval, expected.eval);
+            assert_eq!(actual.best_move, expected.best_move);
+            // every move made in the search was taken back
+            assert_eq!(b, before);
+        }
+    }
+
+    #[test]
+    fn alpha_beta_agrees_with_minimax_from_start() {
+        let mut b = Board::new(6, 4);
+        for depth in 1..=6 {
+            for side in [Side::South, Side::North] {
+                let expected = evaluate_board(&mut b.clone(), depth, side);
+                let actual = search(&mut b, depth, side, None, &mut SearchContext::new());
+                assert_eq!(actual.eval, expected.eval);
+                assert_eq!(actual.best_move, expected.best_move);
+            }
+        }
+    }
+
+    #[test]
+    fn compact_search_agrees() {
+        let mut rng = Rng::new(15);
+        for _ in 0..200 {
+            let mut b = random_board(&mut rng);
+            let mut compact = CompactBoard::try_from(&b).unwrap();
+            let depth = rng.range(0, 5);
+            let expected = search(&mut b, depth, Side::South, None, &mut SearchContext::new());
+            let actual = search(
+                &mut compact,
+                depth,
+                Side::South,
+                None,
+                &mut SearchContext::new(),
+            );
+            assert_eq!(actual.eval, expected.eval);
+            assert_eq!(actual.best_move, expected.best_move);
+        }
+    }
+
+    #[test]
+    fn transposition_table_does_not_change_results() {
+        // one table shared by all positions, as it is between the moves of a game.
+        let mut table = TranspositionTable::new(12);
+        let mut rng = Rng::new(32);
+        for _ in 0..600 {
+            let mut b = random_board(&mut rng);
+            let side = if rng.range(0, 1) == 0 {
+                Side::South
+            } else {
+                Side::North
+            };
+            let depth = rng.range(0, 5);
+
+            let expected = evaluate_board(&mut b.clone(), depth, side);
+            let budget = SearchBudget::depth(depth);
+            let mut ctx = SearchContext::with_budget(&budget, Some(&mut table));
+            let actual = search(&mut b, depth, side, None, &mut ctx);
+            assert_eq!(actual.eval, expected.eval);
+            assert_eq!(actual.best_move, expected.best_move);
+        }
+    }
+
+    #[test]
+    fn boards_of_different_sizes_do_not_share_table_entries() {
+        // the same beans with an empty hole added on each side are placed differently, so the
+        // second search must not pick up what the first stored.
+        let mut table = TranspositionTable::new(12);
+        let mut rng = Rng::new(51);
+        for _ in 0..300 {
+            let b = random_board(&mut rng);
+            let mut wider = Board::new_with_rules(b.holes() + 1, 0, b.rules());
+            for s in [Side::South, Side::North] {
+                for hole in 0..=b.holes() {
+                    wider.set_beans(s, hole, b.beans(s, hole)).unwrap();
+                }
+            }
+            let depth = rng.range(1, 4);
+            for mut board in [b, wider] {
+                let expected = evaluate_board(&mut board.clone(), depth, Side::South);
+                let budget = SearchBudget::depth(depth);
+                let mut ctx = SearchContext::with_budget(&budget, Some(&mut table));
+                let actual = search(&mut board, depth, Side::South, None, &mut ctx);
+                assert_eq!(actual.eval, expected.eval);
+                assert_eq!(actual.best_move, expected.best_move);
+            }
+        }
+    }
+
+    #[test]
+    fn search_follows_the_rules() {
+        // from hole 1 South captures 10 beans under the standard rules; without captures the
+        // bean sown into the pot from hole 3 is worth more.
+        let mut b = Board::new(3, 0);
+        b.set_beans(Side::North, 1, 10).unwrap();
+        b.set_beans(Side::South, 1, 1).unwrap();
+        b.set_beans(Side::South, 3, 1).unwrap();
+        b.set_beans(Side::North, 2, 9).unwrap();
+        b.set_beans(Side::North, 3, 1).unwrap();
+
+        let standard = search(&mut b, 1, Side::South, None, &mut SearchContext::new());
+        assert_eq!(standard.best_move, Move::new(1).ok());
+        assert_eq!(standard.eval, 10);
+
+        b.set_rules(Rules::kalah().with_capture(CaptureRule::NoCapture));
+        let no_capture = search(&mut b, 1, Side::South, None, &mut SearchContext::new());
+        assert_eq!(no_capture.best_move, Move::new(3).ok());
+        assert_eq!(no_capture.eval, 1);
+    }
+
+    #[test]
+    fn mirrored_positions_have_opposite_values() {
+        let mut rng = Rng::new(44);
+        for _ in 0..200 {
+            let b = random_board(&mut rng);
+            let depth = rng.range(0, 4);
+            let south = evaluate_board(&mut b.clone(), depth, Side::South);
+            let north = evaluate_board(&mut b.mirrored(), depth, Side::North);
+            assert_eq!(north.eval, -south.eval);
+        }
+    }
+
+    #[test]
+    fn a_position_and_its_mirror_share_table_entries() {
+        let b = Board::new(6, 4);
+        let budget = SearchBudget::depth(6);
+        let mut table = TranspositionTable::new(16);
+
+        let mut ctx = SearchContext::with_budget(&budget, Some(&mut table));
+        let south = search(&mut b.clone(), 6, Side::South, None, &mut ctx);
+        let first = ctx.nodes;
+
+        let mut ctx = SearchContext::with_budget(&budget, Some(&mut table));
+        let north = search(&mut b.mirrored(), 6, Side::North, None, &mut ctx);
+        assert_eq!(north.eval, -south.eval);
+        assert_eq!(north.best_move, south.best_move.map(|mv| mv.mirrored(6)));
+        assert!(ctx.nodes < first);
+    }
+
+    #[test]
+    fn transposition_table_saves_nodes() {
+        let mut b = Board::new(6, 4);
+        let budget = SearchBudget::depth(10);
+
+        let mut plain = SearchContext::new();
+        let expected = search(&mut b, 10, Side::South, None, &mut plain);
+
+        let mut table = TranspositionTable::new(16);
+        let mut ctx = SearchContext::with_budget(&budget, Some(&mut table));
+        let actual = search(&mut b, 10, Side::South, None, &mut ctx);
+
+        assert_eq!(actual.eval, expected.eval);
+        assert_eq!(actual.best_move, expected.best_move);
+        assert!(ctx.nodes < plain.nodes);
+    }
+
+    #[test]
+    fn iterative_deepening_matches_fixed_depth_search() {
+        let mut b = Board::new(6, 4);
+        for depth in 1..=7 {
+            let (result, reached, _) =
+                iterative_deepening(&b, Side::South, &SearchBudget::depth(depth), None);
+            let expected = search(&mut b, depth, Side::South, None, &mut SearchContext::new());
+            assert_eq!(reached, depth);
+            assert_eq!(result.eval, expected.eval);
+            assert_eq!(result.best_move, expected.best_move);
         }
+    }
+
+    #[test]
+    fn node_budget_returns_last_finished_depth() {
+        let mut b = Board::new(6, 4);
+        let (result, reached, _) =
+            iterative_deepening(&b, Side::North, &SearchBudget::nodes(20_000), None);
+        assert!(reached > 1 && reached < 20);
+
+        let expected = search(
+            &mut b,
+            reached,
+            Side::North,
+            None,
+            &mut SearchContext::new(),
+        );
+        assert_eq!(result.eval, expected.eval);
+        assert_eq!(result.best_move, expected.best_move);
+    }
+
+    #[test]
+    fn zero_time_budget_still_finds_a_move() {
+        let b = Board::new(12, 6);
+        let (result, reached, _) =
+            iterative_deepening(&b, Side::South, &SearchBudget::time(Duration::ZERO), None);
+        assert!(reached >= 1);
+        assert!(b.beans(Side::South, result.best_move.unwrap().hole()) > 0);
+    }
+
+    #[test]
+    fn solved_positions_stop_deepening() {
+        // the game is over within a few plies, so a generous budget is not used up.
+        let b = Board::new(2, 1);
+        let (_, reached, solved) =
+            iterative_deepening(&b, Side::South, &SearchBudget::depth(100), None);
+        assert!(reached < 10);
+        assert!(solved);
+    }
+
+    #[test]
+    fn resigns_proven_losses_and_offers_proven_draws() {
+        // two holes a side, with beans only in hole 2 of each and in the pots.
+        let endgame = |pots: i32, south_pot: i32, north_pot: i32| {
+            let mut b = Board::new(2, 0);
+            b.set_beans(Side::South, 2, pots).unwrap();
+            b.set_beans(Side::North, 2, pots).unwrap();
+            b.set_beans(Side::South, 0, south_pot).unwrap();
+            b.set_beans(Side::North, 0, north_pot).unwrap();
+            b
+        };
+        let player = SmartPlayer::new_with_budget("Homer", SearchBudget::depth(50));
+
+        // North has more than half the beans in its pot.
+        let lost = endgame(2, 0, 5);
+        assert!(player.should_resign(&lost, Side::South));
+        assert!(!player.offers_draw(&lost, Side::South));
+        assert!(player.choose_move(&lost, Side::South).is_some());
+        assert!(!player.should_resign(&lost.mirrored(), Side::South));
+
+        let drawn = endgame(2, 0, 0);
+        assert!(!player.should_resign(&drawn, Side::South));
+        assert!(player.offers_draw(&drawn, Side::South));
+
+        // nothing is proved at the start of a full-sized game.
+        let player = SmartPlayer::new_with_budget("Homer", SearchBudget::depth(4));
+        let start = Board::new(6, 4);
+        assert!(!player.should_resign(&start, Side::South));
+        assert!(!player.offers_draw(&start, Side::South));
+    }
+
+    #[test]
+    fn draw_offers_are_answered_quickly() {
+        let player =
+            SmartPlayer::new_with_budget("Homer", SearchBudget::time(Duration::from_secs(5)));
+        let budget = player.draw_answer_budget();
+        assert_eq!(budget.nodes, Some(DRAW_ANSWER_NODES));
+        assert_eq!(budget.time, Some(Duration::from_secs(5)));
+
+        let player = SmartPlayer::new_with_budget("Homer", SearchBudget::nodes(100));
+        assert_eq!(player.draw_answer_budget().nodes, Some(100));
+
+        // the search stops at the node limit, well short of what the time would allow.
+        let start = Instant::now();
+        let b = Board::new(6, 4);
+        let player =
+            SmartPlayer::new_with_budget("Homer", SearchBudget::time(Duration::from_secs(60)));
+        player.accepts_draw(&b, Side::North);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn the_clock_limits_the_search_time() {
+        let secs = Duration::from_secs;
+        let player = SmartPlayer::new_with_budget("Homer", SearchBudget::time(secs(5)));
+        assert_eq!(player.budget().time, Some(secs(5)));
+
+        let control = TimeControl::Increment {
+            base: secs(300),
+            increment: secs(4),
+        };
+        player.set_time_left(control, secs(100));
+        assert_eq!(player.budget().time, Some(secs(5)));
+        player.set_time_left(control, secs(10));
+        assert_eq!(player.budget().time, Some(Duration::from_millis(3500)));
+        player.set_time_left(control, secs(4));
+        assert_eq!(player.budget().time, Some(secs(2)));
+        player.set_time_left(TimeControl::PerMove(secs(2)), Duration::ZERO);
+        assert_eq!(player.budget().time, Some(Duration::ZERO));
+
+        // a depth-limited player gets a time limit as well.
+        let player = SmartPlayer::new_with_budget("Homer", SearchBudget::depth(8));
+        player.set_time_left(control, secs(100));
+        assert_eq!(player.budget().time, Some(secs(8)));
+        assert_eq!(player.budget().max_depth, Some(8));
+    }
+
+    #[test]
+    fn swaps_only_into_the_better_position() {
+        let player = SmartPlayer::new_with_budget("Homer", SearchBudget::depth(4));
+        let mut b = Board::new(6, 4);
+        b.set_beans(Side::South, 0, 10).unwrap();
+        assert!(player.should_swap(&b, Side::North));
 
-        let BoardEval { eval, best_move } = evaluate_board(b.clone(), 8, s);
-        println!("{} thinks the evaluation is currently {}", self.name, eval);
-        println!("{} chooses hole {}", self.name, best_move);
-        best_move
+        b.set_beans(Side::South, 0, 0).unwrap();
+        b.set_beans(Side::North, 0, 10).unwrap();
+        assert!(!player.should_swap(&b, Side::North));
     }
 }
@@ -0,0 +1,111 @@
+#![allow(dead_code)]
+
+// A fixed-size transposition table for the search, indexed by Board::canonical_key. Each slot
+// holds one entry. A new entry replaces the old one if it is for the same position or comes from
+// a search at least as deep; otherwise the old one is kept, since deeper results save more work.
+
+use crate::board::Move;
+
+// what an entry's value says about the true value of its position.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Bound {
+    // the value is exact.
+    Exact,
+    // the search failed high: the true value is at least this.
+    Lower,
+    // the search failed low: the true value is at most this.
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: i32,
+    pub value: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+    // true if every line below this position reached the end of the game before the depth
+    // limit, so the value would not change with a deeper search.
+    pub solved: bool,
+}
+
+pub struct TranspositionTable {
+    slots: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    // creates a table with 2^size_log2 slots.
+    pub fn new(size_log2: u32) -> Self {
+        TranspositionTable {
+            slots: vec![None; 1 << size_log2],
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key as usize) & (self.slots.len() - 1)
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        match self.slots[self.index(key)] {
+            Some(entry) if entry.key == key => Some(entry),
+            _ => None,
+        }
+    }
+
+    pub fn store(&mut self, entry: Entry) {
+        let index = self.index(entry.key);
+        let replace = match self.slots[index] {
+            None => true,
+            Some(old) => old.key == entry.key || old.depth <= entry.depth,
+        };
+        if replace {
+            self.slots[index] = Some(entry);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn entry(key: u64, depth: i32) -> Entry {
+        Entry {
+            key,
+            depth,
+            value: 7,
+            bound: Bound::Exact,
+            best_move: Move::new(3).ok(),
+            solved: false,
+        }
+    }
+
+    #[test]
+    fn probe_finds_only_matching_keys() {
+        let mut tt = TranspositionTable::new(4);
+        tt.store(entry(5, 2));
+        assert_eq!(tt.probe(5).unwrap().best_move, Move::new(3).ok());
+        // same slot, different position
+        assert!(tt.probe(5 + 16).is_none());
+    }
+
+    #[test]
+    fn deeper_entries_survive_collisions() {
+        let mut tt = TranspositionTable::new(4);
+        tt.store(entry(1, 6));
+        tt.store(entry(1 + 16, 2));
+        assert!(tt.probe(1).is_some());
+        assert!(tt.probe(1 + 16).is_none());
+
+        tt.store(entry(1 + 16, 6));
+        assert!(tt.probe(1).is_none());
+        assert!(tt.probe(1 + 16).is_some());
+
+        tt.clear();
+        assert!(tt.probe(1 + 16).is_none());
+    }
+}
@@ -0,0 +1,44 @@
+use crate::board::Side;
+use crate::rules::Rules;
+
+// Zobrist keys for Kalah positions. A board's hash is the XOR of one key per (side, hole, count)
+// for every pit, so changing a single pit only needs two XORs. Pits can hold any number of
+// beans, so instead of a fixed table of random numbers each key is generated on demand by a
+// strong mixing function; a pit holding zero beans contributes nothing.
+
+// XORed into a board hash when North is to move.
+pub const NORTH_TO_MOVE: u64 = 0x3C6E_F372_FE94_F82B;
+
+fn mix(mut z: u64) -> u64 {
+    // the finalizer of splitmix64
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// the key for side s's hole (0 being the pot) holding `beans` beans.
+pub fn pit_key(s: Side, hole: i32, beans: i32) -> u64 {
+    if beans == 0 {
+        return 0;
+    }
+    let side_bit: u64 = match s {
+        Side::North => 1,
+        Side::South => 0,
+    };
+    mix((beans as u64) << 32 ^ (hole as u64) << 1 ^ side_bit ^ 0x9E37_79B9_7F4A_7C15)
+}
+
+// XORed into a position key to tell apart the same beans under different rules; zero for the
+// standard rules.
+pub fn rules_key(rules: &Rules) -> u64 {
+    match rules.id() {
+        0 => 0,
+        id => mix(id ^ 0x6A09_E667_F3BC_C909),
+    }
+}
+
+// XORed into a position key to tell apart boards with different numbers of holes, whose pits
+// would otherwise hash the same wherever they are empty.
+pub fn size_key(num_holes: i32) -> u64 {
+    mix(num_holes as u64 ^ 0xBB67_AE85_84CA_A73B)
+}